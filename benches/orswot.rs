@@ -0,0 +1,48 @@
+use crdts::{CmRDT, CvRDT, Orswot};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+fn build_set(actor: u8, members: std::ops::Range<u32>) -> Orswot<u32, u8> {
+    let mut set = Orswot::new();
+    for member in members {
+        let op = set.add(member, set.contains(&member).derive_add_ctx(actor));
+        set.apply(op);
+    }
+    set
+}
+
+fn bench_lopsided_merge(c: &mut Criterion) {
+    let large = build_set(1, 0..10_000);
+    let small = build_set(2, 9_990..10_010);
+
+    c.bench_function("merge small delta into large set", |b| {
+        b.iter_batched(
+            || (large.clone(), small.clone()),
+            |(mut large, small)| large.merge(small),
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("merge large set into small delta", |b| {
+        b.iter_batched(
+            || (small.clone(), large.clone()),
+            |(mut small, large)| small.merge(large),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_overlapping_merge(c: &mut Criterion) {
+    let a = build_set(1, 0..10_000);
+    let b = build_set(2, 5_000..15_000);
+
+    c.bench_function("merge overlapping 10k member sets", |bench| {
+        bench.iter_batched(
+            || (a.clone(), b.clone()),
+            |(mut a, b)| a.merge(b),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_lopsided_merge, bench_overlapping_merge);
+criterion_main!(benches);
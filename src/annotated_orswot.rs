@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ctx::{AddCtx, ReadCtx, RmCtx};
+use crate::map::{self, Map};
+use crate::mvreg::{self, MVReg};
+use crate::orswot::{self, Member, Orswot};
+use crate::traits::{CmRDT, CvRDT};
+use crate::vclock::Actor;
+
+/// `AnnotatedOrswot` is an `Orswot` whose members carry a small piece of
+/// metadata, e.g. a label.
+///
+/// Membership is tracked by an `Orswot` and metadata by a `Map` of `MVReg`'s
+/// (an `LWWReg` can't be nested in a `Map`), concurrent writes of metadata
+/// are resolved by keeping the greatest value. Setting metadata (re)adds the
+/// member and removing a member removes its metadata, so a metadata update
+/// concurrent with a remove keeps the member, just like a concurrent add.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnotatedOrswot<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> {
+    members: Orswot<M, A>,
+    meta: Map<M, MVReg<Meta, A>, A>,
+}
+
+/// Op's define an edit to an `AnnotatedOrswot`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> {
+    /// Add a member to the set
+    Add(orswot::Op<M, A>),
+    /// Set the metadata of a member, adding the member if needed
+    SetMeta {
+        /// Adds the member to the set
+        member: orswot::Op<M, A>,
+        /// Writes the metadata
+        meta: map::Op<M, MVReg<Meta, A>, A>,
+    },
+    /// Remove a member and its metadata from the set
+    Rm {
+        /// Removes the member from the set
+        member: orswot::Op<M, A>,
+        /// Removes the metadata
+        meta: map::Op<M, MVReg<Meta, A>, A>,
+    },
+}
+
+impl<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> Default for AnnotatedOrswot<M, Meta, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> CmRDT for AnnotatedOrswot<M, Meta, A> {
+    type Op = Op<M, Meta, A>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            Op::Add(member) => self.members.apply(member),
+            Op::SetMeta { member, meta } | Op::Rm { member, meta } => {
+                self.members.apply(member);
+                self.meta.apply(meta);
+            }
+        }
+    }
+}
+
+impl<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> CvRDT for AnnotatedOrswot<M, Meta, A> {
+    fn merge(&mut self, other: Self) {
+        self.members.merge(other.members);
+        self.meta.merge(other.meta);
+    }
+}
+
+impl<M: Member + Ord, Meta: mvreg::Val + Ord, A: Actor> AnnotatedOrswot<M, Meta, A> {
+    /// Returns a new `AnnotatedOrswot` instance.
+    pub fn new() -> Self {
+        AnnotatedOrswot {
+            members: Orswot::new(),
+            meta: Map::new(),
+        }
+    }
+
+    /// Add a single member, keeping any metadata it already has.
+    pub fn add(&self, member: M, ctx: AddCtx<A>) -> Op<M, Meta, A> {
+        Op::Add(self.members.add(member, ctx))
+    }
+
+    /// Set the metadata of a member, adding the member if it is absent.
+    pub fn set_meta(&self, member: M, meta: Meta, ctx: AddCtx<A>) -> Op<M, Meta, A> {
+        Op::SetMeta {
+            member: self.members.add(member.clone(), ctx.clone()),
+            meta: self
+                .meta
+                .update(member, ctx, |reg, ctx| reg.write(meta, ctx)),
+        }
+    }
+
+    /// Remove a member and its metadata with a witnessing ctx.
+    pub fn rm(&self, member: M, ctx: RmCtx<A>) -> Op<M, Meta, A> {
+        Op::Rm {
+            member: self.members.rm(member.clone(), ctx.clone()),
+            meta: self.meta.rm(member, ctx),
+        }
+    }
+
+    /// Check if the set contains a member
+    pub fn contains(&self, member: &M) -> ReadCtx<bool, A> {
+        self.read_member(member, |present, _| present)
+    }
+
+    /// Retrieve the metadata of a member, `None` if the member is absent or
+    /// has no metadata.
+    pub fn get_meta(&self, member: &M) -> ReadCtx<Option<Meta>, A> {
+        self.read_member(member, |present, reg| {
+            reg.filter(|_| present)
+                .and_then(|reg| reg.read().val.into_iter().max())
+        })
+    }
+
+    /// Build a `ReadCtx` spanning both the membership and the metadata of a
+    /// member, ops derived from it touch both.
+    fn read_member<V>(
+        &self,
+        member: &M,
+        read: impl FnOnce(bool, Option<MVReg<Meta, A>>) -> V,
+    ) -> ReadCtx<V, A> {
+        let members_ctx = self.members.contains(member);
+        let meta_ctx = self.meta.get(member);
+
+        let mut add_clock = members_ctx.add_clock;
+        add_clock.merge(meta_ctx.add_clock);
+        let mut rm_clock = members_ctx.rm_clock;
+        rm_clock.merge(meta_ctx.rm_clock);
+
+        ReadCtx {
+            add_clock,
+            rm_clock,
+            val: read(members_ctx.val, meta_ctx.val),
+        }
+    }
+}
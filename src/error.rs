@@ -12,28 +12,46 @@ pub enum Error {
     /// Instead, users must design their system in a way that will make these
     /// dot collisions unlikely / impossible.
     ConflictingMarker,
-}
 
-impl error::Error for Error {
-    fn description(&self) -> &str {
-        match self {
-            Error::ConflictingMarker => "Dot's are used exactly once for the lifetime of a CRDT",
-        }
-    }
-    fn cause(&self) -> Option<&dyn error::Error> {
-        match self {
-            Error::ConflictingMarker => None,
-        }
-    }
+    /// The internal state of a CRDT violates one of its invariants, e.g.
+    /// after deserializing corrupted data.
+    InconsistentState,
+
+    /// A clock was built with a zero counter, absent actors have an implied
+    /// count of 0 and are never stored.
+    ZeroCounter,
+
+    /// A clock was built with the same actor more than once.
+    DuplicateActor,
+
+    /// An op's dot skipped one or more counters for its actor, meaning the
+    /// ops in between were never delivered.
+    CausalGap,
+
+    /// A remove's context doesn't cover the current version of what it
+    /// removes, so the remove would only take away part of it.
+    StaleContext,
+
+    /// A versioned op was written in a format version this replica doesn't
+    /// know, e.g. by a newer replica during a rolling upgrade.
+    UnsupportedVersion,
 }
 
+impl error::Error for Error {}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::ConflictingMarker => {
-                use std::error::Error;
-                write!(f, "{}", self.description())
-            }
+            Error::ConflictingMarker => write!(
+                f,
+                "Dot's are used exactly once for the lifetime of a CRDT"
+            ),
+            Error::InconsistentState => write!(f, "The CRDT state violates its invariants"),
+            Error::ZeroCounter => write!(f, "Clocks can not hold zero counters"),
+            Error::DuplicateActor => write!(f, "Clocks hold a single counter per actor"),
+            Error::CausalGap => write!(f, "Ops are missing between the clock and this dot"),
+            Error::StaleContext => write!(f, "The remove context is older than the removed value"),
+            Error::UnsupportedVersion => write!(f, "The op is in an unknown format version"),
         }
     }
 }
@@ -1,6 +1,9 @@
+use std::hash::{Hash, Hasher};
+
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
+use crate::map::{Divergence, Fingerprint};
 use crate::traits::{Causal, CmRDT, CvRDT};
 use crate::vclock::{Actor, Dot, VClock};
 
@@ -47,9 +50,17 @@ impl<A: Actor> CvRDT for GCounter<A> {
     }
 }
 
+impl<A: Actor> Divergence for GCounter<A> {}
+
 impl<A: Actor> Causal<A> for GCounter<A> {
     fn forget(&mut self, clock: &VClock<A>) {
-        self.inner.forget(&clock);
+        self.inner.forget(clock);
+    }
+}
+
+impl<A: Actor> Fingerprint for GCounter<A> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        self.hash(state);
     }
 }
 
@@ -66,7 +77,20 @@ impl<A: Actor> GCounter<A> {
         self.inner.inc(actor)
     }
 
-    /// Return the current sum of this counter.
+    /// Merge many counters, e.g. shards of one counter, into this one.
+    ///
+    /// The result only depends on the highest count seen for each actor, so
+    /// the order of `others` doesn't matter.
+    pub fn merge_many(&mut self, others: &[GCounter<A>]) {
+        for other in others {
+            for dot in other.inner.iter() {
+                self.inner.apply(Dot::new(dot.actor.clone(), dot.counter));
+            }
+        }
+    }
+
+    /// Return the current sum of this counter, i.e. the sum over actors of
+    /// the highest count seen for each.
     pub fn read(&self) -> BigUint {
         self.inner.iter().map(|dot| dot.counter).sum()
     }
@@ -90,4 +114,25 @@ mod test {
 
         assert_eq!(a.read(), b.read() + BigUint::from(1u8));
     }
+
+    #[test]
+    fn test_merge_many_sums_shards() {
+        let mut shards = vec![GCounter::new(), GCounter::new(), GCounter::new()];
+        for (i, shard) in shards.iter_mut().enumerate() {
+            for _ in 0..=i {
+                shard.apply(shard.inc(i as u8));
+            }
+        }
+        // a copy of a shard doesn't count twice
+        shards.push(shards[2].clone());
+
+        let mut total = GCounter::new();
+        total.merge_many(&shards);
+        assert_eq!(total.read(), BigUint::from(6u8));
+
+        let mut reversed = GCounter::new();
+        shards.reverse();
+        reversed.merge_many(&shards);
+        assert_eq!(reversed, total);
+    }
 }
@@ -79,4 +79,68 @@ impl<T: Ord> GSet<T> {
     pub fn contains(&self, element: &T) -> bool {
         self.value.contains(element)
     }
+
+    /// Returns the number of elements in the `GSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crdts::GSet;
+    /// let mut a = GSet::new();
+    /// a.insert(1);
+    /// a.insert(1);
+    /// assert_eq!(a.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns `true` if the `GSet` has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Returns the elements of the `GSet`.
+    pub fn read(&self) -> BTreeSet<T>
+    where
+        T: Clone,
+    {
+        self.value.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(elements: &[u8]) -> GSet<u8> {
+        let mut set = GSet::new();
+        for element in elements {
+            set.apply(*element);
+        }
+        set
+    }
+
+    #[test]
+    fn test_merge_commutes() {
+        let (a, b) = (build(&[1, 2]), build(&[2, 3]));
+
+        let mut ab = a.clone();
+        ab.merge(b.clone());
+        let mut ba = b;
+        ba.merge(a);
+
+        assert_eq!(ab, ba);
+        assert_eq!(ab.read(), vec![1, 2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn test_merge_is_idempotent() {
+        let mut a = build(&[1, 2]);
+        let b = a.clone();
+        a.merge(b.clone());
+
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 2);
+    }
 }
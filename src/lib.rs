@@ -14,7 +14,7 @@ mod error;
 pub use crate::error::Error;
 
 mod traits;
-pub use crate::traits::{Causal, CmRDT, CvRDT, FunkyCmRDT, FunkyCvRDT};
+pub use crate::traits::{Causal, CausalContext, CmRDT, CvRDT, FunkyCmRDT, FunkyCvRDT};
 
 /// This module contains a Last-Write-Wins Register.
 pub mod lwwreg;
@@ -39,11 +39,18 @@ pub mod pncounter;
 /// This module contains a Map with Reset-Remove and Observed-Remove semantics.
 pub mod map;
 
+/// This module contains an Observed-Remove Set whose members carry metadata.
+pub mod annotated_orswot;
+
+/// This module contains a remove-biased Observed-Remove Set.
+pub mod rw_orswot;
+
 /// This module contains context for editing a CRDT.
 pub mod ctx;
 
 // Top-level re-exports for CRDT structures.
 pub use crate::{
+    annotated_orswot::AnnotatedOrswot,
     gcounter::GCounter,
     gset::GSet,
     lwwreg::LWWReg,
@@ -51,5 +58,6 @@ pub use crate::{
     mvreg::MVReg,
     orswot::Orswot,
     pncounter::PNCounter,
+    rw_orswot::RWOrswot,
     vclock::{Dot, VClock},
 };
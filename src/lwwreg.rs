@@ -1,8 +1,10 @@
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::{self, Error, Result};
+use crate::map::{Divergence, Fingerprint};
 use crate::traits::{FunkyCmRDT, FunkyCvRDT};
 
 /// Trait bound alias for lwwreg vals
@@ -53,6 +55,8 @@ impl<V: Val, M: Marker> FunkyCvRDT for LWWReg<V, M> {
     }
 }
 
+impl<V: Val, M: Marker> Divergence for LWWReg<V, M> {}
+
 impl<V: Val, M: Marker> FunkyCmRDT for LWWReg<V, M> {
     type Error = error::Error;
     // LWWReg's are small enough that we can replicate
@@ -64,6 +68,12 @@ impl<V: Val, M: Marker> FunkyCmRDT for LWWReg<V, M> {
     }
 }
 
+impl<V: Val + Hash, M: Marker> Fingerprint for LWWReg<V, M> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        self.val.hash(state);
+    }
+}
+
 impl<V: Val, M: Marker> LWWReg<V, M> {
     /// Updates value witnessed by the given marker.
     /// An Err is returned if the given marker is exactly
@@ -117,6 +127,15 @@ mod test {
         assert_eq!(reg, LWWReg { val: "", marker: 0 });
     }
 
+    #[test]
+    fn test_find_divergence() {
+        let reg = LWWReg { val: 1, marker: 2 };
+        assert_eq!(reg.find_divergence(&reg.clone()), None);
+
+        let other = LWWReg { val: 3, marker: 4 };
+        assert_eq!(reg.find_divergence(&other), Some(vec![vec![]]));
+    }
+
     #[test]
     fn test_update() {
         let mut reg = LWWReg {
@@ -145,6 +164,36 @@ mod test {
         assert_eq!(reg, LWWReg { val: 32, marker: 2 });
     }
 
+    #[test]
+    fn test_apply_all_stops_at_first_error() {
+        let mut reg = LWWReg { val: 1, marker: 1 };
+        let ops = vec![
+            LWWReg { val: 2, marker: 2 },
+            LWWReg { val: 3, marker: 2 },
+            LWWReg { val: 4, marker: 3 },
+        ];
+
+        assert_eq!(
+            FunkyCmRDT::apply_all(&mut reg, ops),
+            Err(Error::ConflictingMarker)
+        );
+        assert_eq!(reg, LWWReg { val: 2, marker: 2 });
+    }
+
+    #[test]
+    fn test_merge_with_conflicting_marker_fails_both_ways() {
+        let r1 = LWWReg { val: 1, marker: 7 };
+        let r2 = LWWReg { val: 2, marker: 7 };
+
+        let mut merged = r1.clone();
+        assert_eq!(merged.merge(r2.clone()), Err(Error::ConflictingMarker));
+        assert_eq!(merged, r1);
+
+        let mut merged = r2.clone();
+        assert_eq!(merged.merge(r1), Err(Error::ConflictingMarker));
+        assert_eq!(merged, r2);
+    }
+
     fn build_from_prim(prim: (u8, u16)) -> LWWReg<u8, (u16, u8)> {
         // we make the marker a tuple so that we avoid conflicts
         LWWReg {
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::mem;
 
 use serde::{Deserialize, Serialize};
@@ -24,6 +26,49 @@ where
 {
 }
 
+/// A path of keys from the root of a nested `Map` down to a value, each key
+/// is rendered with its `Debug` representation.
+pub type KeyPath = Vec<String>;
+
+/// Locate where two replicas of a (possibly nested) CRDT differ.
+///
+/// This is a debugging aid for replicas that should have converged but
+/// haven't, it is not meant for hot paths. By default values are compared
+/// as a whole and a difference is reported at the empty path.
+pub trait Divergence: PartialEq {
+    /// The paths to the values that differ between `self` and `other`,
+    /// `None` if the two are equal.
+    fn find_divergence(&self, other: &Self) -> Option<Vec<KeyPath>> {
+        if self == other {
+            None
+        } else {
+            Some(vec![KeyPath::new()])
+        }
+    }
+}
+
+/// Hash the resolved, i.e. observable, value of a CRDT, used by
+/// `Map::fingerprint` to cover the values nested in a map.
+pub trait Fingerprint {
+    /// Feed the resolved value into `state`, equal values must hash equally
+    /// however the replica reached them.
+    fn hash_resolved<H: Hasher>(&self, state: &mut H);
+}
+
+/// Hash items independently of the order they are yielded in, e.g. the
+/// members of a hash set.
+pub(crate) fn hash_unordered<T: Hash, H: Hasher>(items: impl Iterator<Item = T>, state: &mut H) {
+    let mut hashes: Vec<u64> = items
+        .map(|item| {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    hashes.sort_unstable();
+    hashes.hash(state);
+}
+
 /// Map CRDT - Supports Composition of CRDT's with reset-remove semantics.
 ///
 /// Reset-remove means that if one replica removes an entry while another
@@ -72,6 +117,20 @@ pub enum Op<K: Key, V: Val<A>, A: Actor> {
     },
 }
 
+/// What happened to an entry of the Map during `Map::merge_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeEvent {
+    /// The entry was only in the other map and has been added
+    Added,
+    /// The entry was in both maps and their values have been merged
+    Merged,
+    /// Edits to the entry were removed by one of the maps, the entry was
+    /// kept with the remaining, concurrent, edits
+    Reset,
+    /// The entry was removed by one of the maps and has been dropped
+    Dropped,
+}
+
 impl<V: Val<A>, A: Actor> Default for Entry<V, A> {
     fn default() -> Self {
         Self {
@@ -89,11 +148,11 @@ impl<K: Key, V: Val<A>, A: Actor> Default for Map<K, V, A> {
 
 impl<K: Key, V: Val<A>, A: Actor> Causal<A> for Map<K, V, A> {
     fn forget(&mut self, clock: &VClock<A>) {
-        self.entries = mem::replace(&mut self.entries, BTreeMap::new())
+        self.entries = mem::take(&mut self.entries)
             .into_iter()
             .filter_map(|(key, mut entry)| {
-                entry.clock.forget(&clock);
-                entry.val.forget(&clock);
+                entry.clock.forget(clock);
+                entry.val.forget(clock);
                 if entry.clock.is_empty() {
                     None // remove this entry since its been forgotten
                 } else {
@@ -102,10 +161,10 @@ impl<K: Key, V: Val<A>, A: Actor> Causal<A> for Map<K, V, A> {
             })
             .collect();
 
-        self.deferred = mem::replace(&mut self.deferred, HashMap::new())
+        self.deferred = mem::take(&mut self.deferred)
             .into_iter()
             .filter_map(|(mut rm_clock, key)| {
-                rm_clock.forget(&clock);
+                rm_clock.forget(clock);
                 if rm_clock.is_empty() {
                     None // this deferred remove has been forgotten
                 } else {
@@ -114,7 +173,7 @@ impl<K: Key, V: Val<A>, A: Actor> Causal<A> for Map<K, V, A> {
             })
             .collect();
 
-        self.clock.forget(&clock);
+        self.clock.forget(clock);
     }
 }
 
@@ -144,7 +203,46 @@ impl<K: Key, V: Val<A>, A: Actor> CmRDT for Map<K, V, A> {
 
 impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
     fn merge(&mut self, other: Self) {
-        self.entries = mem::replace(&mut self.entries, BTreeMap::new())
+        self.merge_with(other, |_, _| ());
+    }
+}
+
+impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
+    /// Merge another Map into this one, calling `f` with what happened to
+    /// each entry the other map knows about. Entries of this map which the
+    /// other map has not seen at all are not reported, unless a deferred
+    /// remove reaches them.
+    ///
+    /// This converges exactly like `merge`, which is `merge_with` with a
+    /// callback doing nothing.
+    pub fn merge_with<F: FnMut(&K, MergeEvent)>(&mut self, other: Self, mut f: F) {
+        // true if `removed` has seen, and so removed, any of the dots in `clock`
+        fn is_reset<A: Actor>(clock: &VClock<A>, removed: &VClock<A>) -> bool {
+            clock
+                .iter()
+                .any(|dot| removed.get(dot.actor) >= dot.counter)
+        }
+
+        // keys a deferred remove may still drop or reset, their events are
+        // held until the removes are applied
+        let mut pending: BTreeMap<K, Option<MergeEvent>> = other
+            .deferred
+            .values()
+            .chain(self.deferred.values())
+            .flatten()
+            .map(|key| (key.clone(), None))
+            .collect();
+        let seen: BTreeSet<K> = pending
+            .keys()
+            .filter(|key| self.entries.contains_key(key) || other.entries.contains_key(key))
+            .cloned()
+            .collect();
+        let mut emit = |key: &K, event| match pending.get_mut(key) {
+            Some(held) => *held = Some(event),
+            None => f(key, event),
+        };
+
+        self.entries = mem::take(&mut self.entries)
             .into_iter()
             .filter_map(|(key, mut entry)| {
                 if !other.entries.contains_key(&key) {
@@ -153,12 +251,16 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
                     //  2. hasn't seen it
                     if other.clock >= entry.clock {
                         // other has seen this entry and dropped it
+                        emit(&key, MergeEvent::Dropped);
                         None
                     } else {
                         // the other map has not seen this version of this
                         // entry, so add it. But first, we have to remove any
                         // information that may have been known at some point
                         // by the other map about this key and was removed.
+                        if is_reset(&entry.clock, &other.clock) {
+                            emit(&key, MergeEvent::Reset);
+                        }
                         entry.clock.forget(&other.clock);
                         let mut removed_information = other.clock.clone();
                         removed_information.forget(&entry.clock);
@@ -182,6 +284,7 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
                 if common.is_empty() {
                     // both maps had seen each others entry and removed them
                     self.entries.remove(&key).unwrap();
+                    emit(&key, MergeEvent::Dropped);
                 } else {
                     // we should not drop, as there is information still tracked in
                     // the common clock.
@@ -190,6 +293,11 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
                     let mut information_that_was_deleted = entry.clock.clone();
                     information_that_was_deleted.merge(our_entry.clock.clone());
                     information_that_was_deleted.forget(&common);
+                    if information_that_was_deleted.is_empty() {
+                        emit(&key, MergeEvent::Merged);
+                    } else {
+                        emit(&key, MergeEvent::Reset);
+                    }
                     our_entry.val.forget(&information_that_was_deleted);
                     our_entry.clock = common;
                 }
@@ -199,10 +307,16 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
                 //  2. have not seen it
                 if self.clock >= entry.clock {
                     // We've seen this entry and dropped it, we won't add it back
+                    emit(&key, MergeEvent::Dropped);
                 } else {
                     // We have not seen this version of this entry, so we add it.
                     // but first, we have to remove the information on this entry
                     // that we have seen and deleted
+                    if is_reset(&entry.clock, &self.clock) {
+                        emit(&key, MergeEvent::Reset);
+                    } else {
+                        emit(&key, MergeEvent::Added);
+                    }
                     entry.clock.forget(&self.clock);
 
                     let mut information_we_deleted = self.clock.clone();
@@ -213,6 +327,11 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
             }
         }
 
+        let clocks: BTreeMap<K, VClock<A>> = pending
+            .keys()
+            .filter_map(|key| Some((key.clone(), self.entries.get(key)?.clock.clone())))
+            .collect();
+
         // merge deferred removals
         for (rm_clock, keys) in other.deferred {
             self.apply_keyset_rm(keys, rm_clock);
@@ -221,6 +340,18 @@ impl<K: Key, V: Val<A>, A: Actor> CvRDT for Map<K, V, A> {
         self.clock.merge(other.clock);
 
         self.apply_deferred();
+
+        for (key, held) in pending {
+            let event = match (self.entries.get(&key), clocks.get(&key)) {
+                (None, _) if seen.contains(&key) => Some(MergeEvent::Dropped),
+                (Some(entry), Some(clock)) if &entry.clock != clock => Some(MergeEvent::Reset),
+                (None, _) => None,
+                (Some(_), _) => held,
+            };
+            if let Some(event) = event {
+                f(&key, event);
+            }
+        }
     }
 }
 
@@ -252,10 +383,28 @@ impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
         }
     }
 
+    /// Iterate, in order, over the keys of the entries in the Map without
+    /// cloning them.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+
+    /// Check if the Map has an entry under a key, without cloning its value
+    pub fn contains_key(&self, key: &K) -> ReadCtx<bool, A> {
+        let entry_opt = self.entries.get(key);
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: entry_opt
+                .map(|map_entry| map_entry.clock.clone())
+                .unwrap_or_default(),
+            val: entry_opt.is_some(),
+        }
+    }
+
     /// Retrieve value stored under a key
     pub fn get(&self, key: &K) -> ReadCtx<Option<V>, A> {
         let add_clock = self.clock.clone();
-        let entry_opt = self.entries.get(&key);
+        let entry_opt = self.entries.get(key);
         ReadCtx {
             add_clock,
             rm_clock: entry_opt
@@ -265,6 +414,59 @@ impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
         }
     }
 
+    /// Retrieve value stored under a key, or `V::default()` if the key is
+    /// absent, e.g. to treat the value as an accumulator.
+    pub fn get_or_default(&self, key: &K) -> ReadCtx<V, A> {
+        let ReadCtx {
+            add_clock,
+            rm_clock,
+            val,
+        } = self.get(key);
+        ReadCtx {
+            add_clock,
+            rm_clock,
+            val: val.unwrap_or_default(),
+        }
+    }
+
+    /// Materialize the current value under each key into a plain map,
+    /// dropping all CRDT metadata.
+    ///
+    /// This is a one-way export, the snapshot can not be merged back.
+    pub fn snapshot(&self) -> BTreeMap<K, V> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.val.clone()))
+            .collect()
+    }
+
+    /// A fingerprint of this map's state, replicas that have converged share
+    /// a fingerprint regardless of the order they merged in.
+    ///
+    /// The fingerprint covers the map clock, each key with its entry clock and
+    /// resolved value, and the pending removes, so replicas with the same
+    /// causal history but different values, e.g. after a reused dot, differ.
+    /// Fingerprints are only comparable within one build, as the underlying
+    /// hasher is not stable across Rust releases.
+    pub fn fingerprint(&self) -> u64
+    where
+        K: Hash,
+        V: Fingerprint,
+    {
+        let mut hasher = DefaultHasher::new();
+        self.clock.hash(&mut hasher);
+        for (key, entry) in self.entries.iter() {
+            key.hash(&mut hasher);
+            entry.clock.hash(&mut hasher);
+            entry.val.hash_resolved(&mut hasher);
+        }
+
+        // deferred is a HashMap, hash its entries in a canonical order
+        hash_unordered(self.deferred.iter(), &mut hasher);
+
+        hasher.finish()
+    }
+
     /// Update a value under some key, if the key is not present in the map,
     /// the updater will be given the result of V::default().
     pub fn update<F, I>(&self, key: I, ctx: AddCtx<A>, f: F) -> Op<K, V, A>
@@ -275,26 +477,55 @@ impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
         let key = key.into();
         let dot = ctx.dot.clone();
         let op = match self.entries.get(&key).map(|e| &e.val) {
-            Some(data) => f(&data, ctx),
+            Some(data) => f(data, ctx),
             None => f(&V::default(), ctx),
         };
 
         Op::Up { dot, key, op }
     }
 
+    /// Apply the same update to several keys, producing one op per key.
+    ///
+    /// The first op uses the dot of `ctx`, each following op the next dot of
+    /// the same actor, so the ops must be applied in order.
+    pub fn update_all<F, I>(&self, keys: I, mut ctx: AddCtx<A>, f: F) -> Vec<Op<K, V, A>>
+    where
+        F: Fn(&V, AddCtx<A>) -> V::Op,
+        I: IntoIterator<Item = K>,
+    {
+        let mut ops = Vec::new();
+        for key in keys {
+            ops.push(self.update(key, ctx.clone(), &f));
+            ctx.dot = ctx.dot.increment();
+            ctx.clock.apply(ctx.dot.clone());
+        }
+        ops
+    }
+
     /// Remove an entry from the Map
     pub fn rm(&self, key: impl Into<K>, ctx: RmCtx<A>) -> Op<K, V, A> {
         let mut keyset = BTreeSet::new();
         keyset.insert(key.into());
         Op::Rm {
             clock: ctx.clock,
-            keyset: keyset,
+            keyset,
+        }
+    }
+
+    /// Remove an entry from the Map, `None` if the key is absent and the
+    /// remove would be a no-op.
+    pub fn rm_checked(&self, key: impl Into<K>, ctx: RmCtx<A>) -> Option<Op<K, V, A>> {
+        let key = key.into();
+        if self.entries.contains_key(&key) {
+            Some(self.rm(key, ctx))
+        } else {
+            None
         }
     }
 
     /// apply the pending deferred removes
     fn apply_deferred(&mut self) {
-        let deferred = mem::replace(&mut self.deferred, HashMap::new());
+        let deferred = mem::take(&mut self.deferred);
         for (clock, keys) in deferred {
             self.apply_keyset_rm(keys, clock);
         }
@@ -303,12 +534,12 @@ impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
     /// Apply a set of key removals given a clock.
     fn apply_keyset_rm(&mut self, mut keyset: BTreeSet<K>, clock: VClock<A>) {
         for key in keyset.iter() {
-            if let Some(entry) = self.entries.get_mut(&key) {
+            if let Some(entry) = self.entries.get_mut(key) {
                 entry.clock.forget(&clock);
                 if entry.clock.is_empty() {
                     // The entry clock says we have no info on this entry.
                     // So remove the entry
-                    self.entries.remove(&key);
+                    self.entries.remove(key);
                 } else {
                     // The entry clock is not empty so this means we still
                     // have some information on this entry, keep it.
@@ -333,11 +564,60 @@ impl<K: Key, V: Val<A>, A: Actor> Map<K, V, A> {
     }
 }
 
+impl<K: Key + Hash, V: Val<A> + Fingerprint, A: Actor> Fingerprint for Map<K, V, A> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        for (key, entry) in self.entries.iter() {
+            key.hash(state);
+            entry.val.hash_resolved(state);
+        }
+    }
+}
+
+impl<K: Key, V: Val<A> + Divergence, A: Actor> Divergence for Map<K, V, A> {
+    /// Descends into the values under each key, reporting the path to every
+    /// key that is missing on one side or whose value differs.
+    fn find_divergence(&self, other: &Self) -> Option<Vec<KeyPath>> {
+        let keys: BTreeSet<&K> = self.entries.keys().chain(other.entries.keys()).collect();
+
+        let mut paths = Vec::new();
+        for key in keys {
+            let diverged = match (self.entries.get(key), other.entries.get(key)) {
+                (Some(ours), Some(theirs)) => {
+                    let nested = ours.val.find_divergence(&theirs.val);
+                    if nested.is_none() && ours.clock != theirs.clock {
+                        Some(vec![KeyPath::new()])
+                    } else {
+                        nested
+                    }
+                }
+                _ => Some(vec![KeyPath::new()]),
+            };
+
+            for mut path in diverged.into_iter().flatten() {
+                path.insert(0, format!("{:?}", key));
+                paths.push(path);
+            }
+        }
+
+        if paths.is_empty() && self != other {
+            // the entries agree, so the map clock or pending removes differ
+            paths.push(KeyPath::new());
+        }
+
+        if paths.is_empty() {
+            None
+        } else {
+            Some(paths)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::mvreg::{self, MVReg};
     use crate::orswot::Orswot;
+    use std::marker::PhantomData;
 
     type TestActor = u8;
     type TestKey = u8;
@@ -441,6 +721,7 @@ mod test {
                         .into_iter()
                         .collect(),
                         deferred: HashMap::new(),
+                        actor: PhantomData,
                     },
                 },
             )]
@@ -464,7 +745,8 @@ mod test {
                             entries: vec![(2, VClock::from(Dot::new(93, 1)))]
                                 .into_iter()
                                 .collect(),
-                            deferred: HashMap::new()
+                            deferred: HashMap::new(),
+                            actor: PhantomData,
                         }
                     }
                 )]
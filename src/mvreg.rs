@@ -1,10 +1,12 @@
 use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
 use std::mem;
 
 use serde::{Deserialize, Serialize};
 
 use crate::ctx::{AddCtx, ReadCtx};
+use crate::map::{self, Divergence, Fingerprint};
 use crate::traits::{Causal, CmRDT, CvRDT};
 use crate::vclock::{Actor, VClock};
 
@@ -96,7 +98,7 @@ impl<V: Val, A: Actor> Causal<A> for MVReg<V, A> {
             .clone()
             .into_iter()
             .filter_map(|(mut val_clock, val)| {
-                val_clock.forget(&clock);
+                val_clock.forget(clock);
                 if val_clock.is_empty() {
                     None // remove this value from the register
                 } else {
@@ -115,7 +117,7 @@ impl<V: Val, A: Actor> Default for MVReg<V, A> {
 
 impl<V: Val, A: Actor> CvRDT for MVReg<V, A> {
     fn merge(&mut self, other: Self) {
-        self.vals = mem::replace(&mut self.vals, Vec::new())
+        self.vals = mem::take(&mut self.vals)
             .into_iter()
             .filter(|(clock, _)| other.vals.iter().filter(|(c, _)| clock < c).count() == 0)
             .collect();
@@ -131,6 +133,8 @@ impl<V: Val, A: Actor> CvRDT for MVReg<V, A> {
     }
 }
 
+impl<V: Val + PartialEq, A: Actor> Divergence for MVReg<V, A> {}
+
 impl<V: Val, A: Actor> CmRDT for MVReg<V, A> {
     type Op = Op<V, A>;
 
@@ -142,9 +146,8 @@ impl<V: Val, A: Actor> CmRDT for MVReg<V, A> {
                 }
                 // first filter out all values that are dominated by the Op clock
                 self.vals
-                    .retain(|(val_clock, _)| match val_clock.partial_cmp(&clock) {
-                        None | Some(Ordering::Greater) => true,
-                        _ => false,
+                    .retain(|(val_clock, _)| {
+                        matches!(val_clock.partial_cmp(&clock), None | Some(Ordering::Greater))
                     });
 
                 // TAI: in the case were the Op has a context that already was present,
@@ -169,6 +172,13 @@ impl<V: Val, A: Actor> CmRDT for MVReg<V, A> {
     }
 }
 
+/// Concurrent values are hashed regardless of their order.
+impl<V: Val + Hash, A: Actor> Fingerprint for MVReg<V, A> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        map::hash_unordered(self.vals.iter().map(|(_, val)| val), state);
+    }
+}
+
 impl<V: Val, A: Actor> MVReg<V, A> {
     /// Construct a new empty MVReg
     pub fn new() -> Self {
@@ -1,14 +1,21 @@
 /// Observed-Remove Set With Out Tombstones (ORSWOT), ported directly from `riak_dt`.
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::cmp::Ordering;
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
 use std::mem;
+use std::ops::RangeBounds;
+use std::result;
 
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::ctx::{AddCtx, ReadCtx, RmCtx};
-use crate::traits::{Causal, CmRDT, CvRDT};
+use crate::error::{Error, Result};
+use crate::map::{self, Divergence, Fingerprint};
+use crate::traits::{Causal, CausalContext, CmRDT, CvRDT};
 use crate::vclock::{Actor, Dot, VClock};
 
 /// Trait bound alias for members in a set
@@ -17,11 +24,21 @@ impl<T: Debug + Clone + Hash + Eq> Member for T {}
 
 /// `Orswot` is an add-biased or-set without tombstones ported from
 /// the riak_dt CRDT library.
+///
+/// Add-biased means a remove only affects the dots witnessed by its context,
+/// so an add concurrent with a remove survives. A remove-biased set has to
+/// remember removals indefinitely (tombstones) and is a different CRDT,
+/// see `RWOrswot`.
+///
+/// The causal context defaults to a `VClock`, any other `CausalContext`
+/// may be used in its place.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Orswot<M: Member, A: Actor> {
-    pub(crate) clock: VClock<A>,
-    pub(crate) entries: HashMap<M, VClock<A>>,
-    pub(crate) deferred: HashMap<VClock<A>, HashSet<M>>,
+pub struct Orswot<M: Member, A: Actor, C: CausalContext<A> = VClock<A>> {
+    pub(crate) clock: C,
+    pub(crate) entries: HashMap<M, C>,
+    pub(crate) deferred: HashMap<C, HashSet<M>>,
+    #[serde(skip)]
+    pub(crate) actor: PhantomData<A>,
 }
 
 /// Op's define an edit to an Orswot, Op's must be replayed in the exact order
@@ -29,7 +46,7 @@ pub struct Orswot<M: Member, A: Actor> {
 ///
 /// Op's are idempotent, that is, applying an Op twice will not have an effect
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Op<M: Member, A: Actor> {
+pub enum Op<M: Member, A: Actor, C: CausalContext<A> = VClock<A>> {
     /// Add a member to the set
     Add {
         /// witnessing dot
@@ -39,21 +56,97 @@ pub enum Op<M: Member, A: Actor> {
     },
     /// Remove a member from the set
     Rm {
-        /// witnessing clock
-        clock: VClock<A>,
+        /// witnessing clock, compacted with the `compact-ops` feature
+        #[cfg_attr(
+            feature = "compact-ops",
+            serde(
+                with = "crate::vclock::compact",
+                bound(
+                    serialize = "C: crate::vclock::CompactSerialize",
+                    deserialize = "C: crate::vclock::CompactDeserialize<'de>"
+                )
+            )
+        )]
+        clock: C,
         /// Member to remove
         members: HashSet<M>,
     },
 }
 
-impl<M: Member, A: Actor> Default for Orswot<M, A> {
+impl<M: Member, A: Actor> Op<M, A> {
+    /// The causal context this op depends on, i.e. what a replica must have
+    /// seen before the op can be applied without being deferred.
+    ///
+    /// For an `Add` this is the dot preceding the op's dot, for an `Rm` it
+    /// is the remove clock.
+    pub fn dependencies(&self) -> VClock<A> {
+        match self {
+            Op::Add { dot, .. } => {
+                let mut deps = VClock::new();
+                if dot.counter > 1 {
+                    deps.apply(Dot::new(dot.actor.clone(), dot.counter - 1));
+                }
+                deps
+            }
+            Op::Rm { clock, .. } => clock.clone(),
+        }
+    }
+
+    /// Tag this op with the current format version, `OP_VERSION`.
+    pub fn into_versioned(self) -> VersionedOp<M, A> {
+        VersionedOp {
+            version: OP_VERSION,
+            op: self,
+        }
+    }
+
+    /// Unwrap a versioned op, failing with `Error::UnsupportedVersion` if it
+    /// was not written in a known format version.
+    pub fn from_versioned(versioned: VersionedOp<M, A>) -> Result<Self> {
+        if versioned.version == OP_VERSION {
+            Ok(versioned.op)
+        } else {
+            Err(Error::UnsupportedVersion)
+        }
+    }
+}
+
+/// The format version of the `Op`'s written by `Op::into_versioned`.
+///
+/// The `compact-ops` feature changes how remove contexts are encoded, so
+/// it has a version of its own and replicas that disagree on the feature
+/// reject each other's versioned op's.
+#[cfg(not(feature = "compact-ops"))]
+pub const OP_VERSION: u32 = 1;
+
+/// The format version of the `Op`'s written by `Op::into_versioned`, see
+/// above; remove contexts are compacted.
+#[cfg(feature = "compact-ops")]
+pub const OP_VERSION: u32 = 2;
+
+/// An `Op` tagged with the version of its wire format, so replicas can
+/// reject ops they don't understand, e.g. during a rolling upgrade.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedOp<M: Member, A: Actor> {
+    /// The format version of `op`
+    pub version: u32,
+    /// The wrapped op
+    pub op: Op<M, A>,
+}
+
+impl<M: Member, A: Actor, C: CausalContext<A>> Default for Orswot<M, A, C> {
     fn default() -> Self {
-        Orswot::new()
+        Orswot {
+            clock: C::default(),
+            entries: HashMap::new(),
+            deferred: HashMap::new(),
+            actor: PhantomData,
+        }
     }
 }
 
-impl<M: Member, A: Actor> CmRDT for Orswot<M, A> {
-    type Op = Op<M, A>;
+impl<M: Member, A: Actor, C: CausalContext<A>> CmRDT for Orswot<M, A, C> {
+    type Op = Op<M, A, C>;
 
     fn apply(&mut self, op: Self::Op) {
         match op {
@@ -74,43 +167,738 @@ impl<M: Member, A: Actor> CmRDT for Orswot<M, A> {
             }
         }
     }
+
+    fn apply_changed(&mut self, op: Self::Op) -> bool {
+        let changed = match &op {
+            Op::Add { dot, .. } => self.clock.get(&dot.actor) < dot.counter,
+            Op::Rm { clock, members } => {
+                let trims_a_member = members.iter().any(|member| {
+                    self.entries.get(member).is_some_and(|member_clock| {
+                        member_clock.clone_without(clock) != *member_clock
+                    })
+                });
+                let defers = match clock.partial_cmp(&self.clock) {
+                    None | Some(Ordering::Greater) => !self
+                        .deferred
+                        .get(clock)
+                        .is_some_and(|deferred| members.is_subset(deferred)),
+                    _ => false,
+                };
+                trims_a_member || defers
+            }
+        };
+        self.apply(op);
+        changed
+    }
 }
 
-impl<M: Member, A: Actor> CvRDT for Orswot<M, A> {
+impl<M: Member, A: Actor, C: CausalContext<A>> CvRDT for Orswot<M, A, C> {
     /// Merge combines another `Orswot` with this one.
     fn merge(&mut self, other: Self) {
-        self.entries = mem::replace(&mut self.entries, HashMap::new())
+        self.merge_inner(other, |_| ());
+    }
+}
+
+impl<M: Member, A: Actor, C: CausalContext<A>> Divergence for Orswot<M, A, C> {}
+
+impl<M: Member, A: Actor, C: CausalContext<A>> Causal<A> for Orswot<M, A, C> {
+    fn forget(&mut self, clock: &VClock<A>) {
+        self.clock.forget(clock);
+
+        self.entries.retain(|_, val_clock| {
+            val_clock.forget(clock);
+            !val_clock.is_empty()
+        });
+
+        for (mut vclock, deferred) in mem::take(&mut self.deferred) {
+            vclock.forget(clock);
+            if !vclock.is_empty() {
+                // forgetting may map distinct clocks to the same one
+                self.deferred.entry(vclock).or_default().extend(deferred);
+            }
+        }
+    }
+}
+
+/// Members are hashed regardless of their order.
+impl<M: Member, A: Actor, C: CausalContext<A>> Fingerprint for Orswot<M, A, C> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        map::hash_unordered(self.entries.keys(), state);
+    }
+}
+
+impl<M: Member, A: Actor> Orswot<M, A> {
+    /// Returns a new `Orswot` instance.
+    pub fn new() -> Self {
+        Orswot {
+            clock: VClock::new(),
+            entries: HashMap::new(),
+            deferred: HashMap::new(),
+            actor: PhantomData,
+        }
+    }
+
+    /// Build a set holding `members`, each added by `actor` under its own dot.
+    ///
+    /// Intended for tests and seeding, use a single actor per construction
+    /// and not one that is also editing a live replica, as that replica's
+    /// dots would collide with ours.
+    pub fn from_value(members: impl IntoIterator<Item = M>, actor: A) -> Self {
+        let mut set = Self::new();
+        for member in members {
+            let dot = set.clock.inc(actor.clone());
+            set.apply(Op::Add { dot, member });
+        }
+        set
+    }
+
+    /// Merge `other` while ignoring any dots from `frozen` actors that this
+    /// set has not already seen, e.g. so that a decommissioned replica can't
+    /// introduce new members after a cutover.
+    ///
+    /// This stays a CRDT among the non-frozen actors only if every replica
+    /// freezes the same actors at the same point, i.e. after having seen the
+    /// same dots from them. Coordinating that cutover is up to the cluster.
+    pub fn merge_freezing(&mut self, mut other: Self, frozen: &BTreeSet<A>) {
+        fn clamp<A: Actor>(clock: &mut VClock<A>, actor: &A, counter: u64) {
+            if counter == 0 {
+                clock.dots.remove(actor);
+            } else {
+                clock.dots.insert(actor.clone(), counter);
+            }
+        }
+
+        for actor in frozen {
+            let seen = self.clock.get(actor);
+            if other.clock.get(actor) <= seen {
+                continue;
+            }
+
+            for (member, clock) in other.entries.iter_mut() {
+                if clock.get(actor) > seen {
+                    // fall back to the dot we have for this member, if any
+                    let ours = self.entries.get(member).map_or(0, |c| c.get(actor));
+                    clamp(clock, actor, ours);
+                }
+            }
+            other.entries.retain(|_, clock| !clock.is_empty());
+
+            for (mut clock, members) in mem::take(&mut other.deferred) {
+                if clock.get(actor) > seen {
+                    clamp(&mut clock, actor, seen);
+                }
+                if !clock.is_empty() {
+                    other.deferred.entry(clock).or_default().extend(members);
+                }
+            }
+
+            clamp(&mut other.clock, actor, seen);
+        }
+
+        self.merge(other);
+    }
+
+    /// Attribute the dots of actor `from` to actor `to`, across the set
+    /// clock, the member clocks and the deferred removes, e.g. when a
+    /// replica's actor id changes.
+    ///
+    /// Dots identify a single edit, so `to` must be an actor this set has
+    /// never seen, otherwise the set is left untouched and
+    /// `Error::ConflictingMarker` is returned. Every replica has to rename
+    /// the actor for them to keep converging.
+    pub fn rename_actor(&mut self, from: &A, to: A) -> Result<()> {
+        if self.clock.get(&to) > 0 {
+            return Err(Error::ConflictingMarker);
+        }
+
+        self.clock.rename_actor(from, to.clone());
+        for clock in self.entries.values_mut() {
+            clock.rename_actor(from, to.clone());
+        }
+        for (mut clock, members) in mem::take(&mut self.deferred) {
+            clock.rename_actor(from, to.clone());
+            self.deferred.entry(clock).or_default().extend(members);
+        }
+        Ok(())
+    }
+
+    /// Add a single element.
+    pub fn add(&self, member: M, ctx: AddCtx<A>) -> Op<M, A> {
+        Op::Add {
+            dot: ctx.dot,
+            member,
+        }
+    }
+
+    /// Remove a member with a witnessing ctx.
+    pub fn rm(&self, member: M, ctx: RmCtx<A>) -> Op<M, A> {
+        let mut members = HashSet::new();
+        members.insert(member);
+        Op::Rm {
+            clock: ctx.clock,
+            members,
+        }
+    }
+
+    /// Remove a member with a witnessing ctx, returning
+    /// `Error::StaleContext` if the ctx doesn't cover the member's clock.
+    ///
+    /// A remove with a stale ctx leaves the dots it hasn't seen in place, so
+    /// the member stays in the set, which `rm` does silently.
+    pub fn rm_checked(&self, member: M, ctx: RmCtx<A>) -> Result<Op<M, A>> {
+        let covered = self
+            .entries
+            .get(&member)
+            .is_none_or(|clock| *clock <= ctx.clock);
+        if covered {
+            Ok(self.rm(member, ctx))
+        } else {
+            Err(Error::StaleContext)
+        }
+    }
+
+    /// Remove a member using its own clock as the witnessing ctx, i.e.
+    /// remove the version of the member that this set currently sees.
+    ///
+    /// Concurrent (re-)adds of the member are not removed.
+    pub fn rm_member(&self, member: &M) -> Op<M, A> {
+        // built from the member clock alone, `contains` would clone the set
+        // clock too
+        let clock = self.entries.get(member).cloned().unwrap_or_default();
+        self.rm(member.clone(), RmCtx { clock })
+    }
+
+    /// Remove every current member with a witnessing ctx, usually derived
+    /// from `read`.
+    ///
+    /// This behaves like removing each member individually: members added
+    /// concurrently with, or after, the ctx survive.
+    pub fn clear(&self, ctx: RmCtx<A>) -> Op<M, A> {
+        Op::Rm {
+            clock: ctx.clock,
+            members: self.entries.keys().cloned().collect(),
+        }
+    }
+
+    /// Remove every member for which `pred` returns false.
+    ///
+    /// Each remove is witnessed by the member's own clock, so a concurrent
+    /// re-add of a removed member survives.
+    pub fn retain<F: Fn(&M) -> bool>(&self, pred: F) -> Vec<Op<M, A>> {
+        self.entries
+            .keys()
+            .filter(|member| !pred(member))
+            .map(|member| self.rm_member(member))
+            .collect()
+    }
+
+    /// The ops that bring this set's members in line with `target`'s.
+    ///
+    /// Every dot of `target`'s members that we haven't seen is added, in
+    /// counter order so that no add is mistaken for one we've already seen,
+    /// and members missing from `target` are removed with their own clocks.
+    /// A member we have removed but `target` still holds is not re-added,
+    /// since we've already seen its dots, `merge` is needed for that.
+    pub fn diff(&self, target: &Self) -> Vec<Op<M, A>> {
+        let mut adds: Vec<(M, Dot<A>)> = Vec::new();
+        for (member, clock) in target.entries.iter() {
+            for dot in clock.iter() {
+                if dot.counter > self.clock.get(dot.actor) {
+                    adds.push((member.clone(), Dot::new(dot.actor.clone(), dot.counter)));
+                }
+            }
+        }
+        adds.sort_by_key(|(_, dot)| dot.counter);
+
+        let rms = self
+            .entries
+            .keys()
+            .filter(|member| !target.entries.contains_key(member))
+            .map(|member| self.rm_member(member));
+        adds.into_iter()
+            .map(|(member, dot)| Op::Add { dot, member })
+            .chain(rms)
+            .collect()
+    }
+
+    /// Check if the set contains a member
+    ///
+    /// The ctx is read from two clocks: the `add_clock` is the set clock,
+    /// from which `derive_add_ctx` picks the next dot of an actor, and the
+    /// `rm_clock` is the member's clock, which is empty if the member is
+    /// absent. See `context_ref` to borrow the member clock instead.
+    pub fn contains(&self, member: &M) -> ReadCtx<bool, A> {
+        let member_clock_opt = self.entries.get(member);
+        let exists = member_clock_opt.is_some();
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: member_clock_opt.cloned().unwrap_or_default(),
+            val: exists,
+        }
+    }
+
+    /// Retrieve the current members.
+    pub fn read(&self) -> ReadCtx<HashSet<M>, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.entries.keys().cloned().collect(),
+        }
+    }
+
+    /// Returns the number of members in the set
+    pub fn len(&self) -> ReadCtx<usize, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.entries.len(),
+        }
+    }
+
+    /// Returns true if the set has no members, false otherwise
+    pub fn is_empty(&self) -> ReadCtx<bool, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.entries.is_empty(),
+        }
+    }
+
+    /// The part of this set a peer at `since` is missing, to be used with
+    /// `merge_delta` in place of shipping the full state.
+    ///
+    /// Removes don't advance the set clock, so a peer at `since` may still
+    /// hold dots that were removed here from any member. Every member is
+    /// therefore sent along with its clock, whatever `since` is.
+    pub fn delta(&self, _since: &VClock<A>) -> OrswotDelta<M, A> {
+        OrswotDelta {
+            clock: self.clock.clone(),
+            entries: self.entries.clone(),
+            deferred: self.deferred.clone(),
+        }
+    }
+
+    /// Merge a delta produced by a peer's `delta`.
+    pub fn merge_delta(&mut self, delta: OrswotDelta<M, A>) {
+        self.merge(Orswot {
+            clock: delta.clock,
+            entries: delta.entries,
+            deferred: delta.deferred,
+            actor: PhantomData,
+        });
+    }
+
+    /// A rough estimate of the size of this set once serialized, in bytes,
+    /// computed without serializing it.
+    ///
+    /// Members and actors are counted at their in-memory size and counters
+    /// at 8 bytes, so the estimate ignores heap data owned by members and
+    /// the overhead of any particular encoding. It is meant for comparing
+    /// sets, e.g. deciding between a full state and a delta sync.
+    pub fn estimated_serialized_size(&self) -> usize {
+        let member = mem::size_of::<M>();
+        let clock = |clock: &VClock<A>| clock.dots.len() * (mem::size_of::<A>() + 8);
+
+        let entries: usize = self.entries.values().map(|c| member + clock(c)).sum();
+        let deferred: usize = self
+            .deferred
+            .iter()
+            .map(|(c, members)| clock(c) + members.len() * member)
+            .sum();
+        clock(&self.clock) + entries + deferred
+    }
+
+    /// Serialize a set in a canonical form, equal sets serialize to the same
+    /// bytes. Meant to be used with
+    /// `#[serde(serialize_with = "Orswot::serialize_canonical")]` or called
+    /// directly, e.g. to hash or compare serialized replicas.
+    ///
+    /// The layout is the one derived for `Orswot`, so the output deserializes
+    /// as usual, only members and deferred removes are sorted.
+    pub fn serialize_canonical<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        M: Ord + Serialize,
+        A: Serialize,
+    {
+        let entries: BTreeMap<&M, &VClock<A>> = self.entries.iter().collect();
+        let mut deferred: Vec<(&VClock<A>, BTreeSet<&M>)> = self
+            .deferred
+            .iter()
+            .map(|(clock, members)| (clock, members.iter().collect()))
+            .collect();
+        deferred.sort_by(|(a, _), (b, _)| a.dots.cmp(&b.dots));
+
+        let mut state = serializer.serialize_struct("Orswot", 3)?;
+        state.serialize_field("clock", &self.clock)?;
+        state.serialize_field("entries", &entries)?;
+        state.serialize_field("deferred", &SortedMap(deferred))?;
+        state.end()
+    }
+
+    /// Estimate how far this set has diverged from a peer's clock.
+    ///
+    /// This is the L1 distance between the two clocks, i.e. the number of
+    /// dots that one side has seen and the other has not. Higher scores mean
+    /// there is more to exchange when syncing.
+    pub fn divergence_score(&self, other: &VClock<A>) -> u64 {
+        let ours = self
+            .clock
+            .iter()
+            .map(|dot| dot.counter.saturating_sub(other.get(dot.actor)));
+        let theirs = other
+            .iter()
+            .map(|dot| dot.counter.saturating_sub(self.clock.get(dot.actor)));
+        ours.chain(theirs).sum()
+    }
+}
+
+impl<M: Member, A: Actor, C: CausalContext<A>> Orswot<M, A, C> {
+    /// Iterate over the members of the set without cloning them, the
+    /// iterator borrows the set so it can't be held across an edit.
+    ///
+    /// Members are stored in a hash map, so the iteration order is arbitrary,
+    /// use `range` for members in order.
+    pub fn iter(&self) -> hash_map::Keys<'_, M, C> {
+        self.entries.keys()
+    }
+
+    /// Iterate over the members along with the clocks witnessing them, for
+    /// replication code that needs more than the members, e.g. to compute
+    /// its own deltas.
+    pub fn entries(&self) -> impl Iterator<Item = (&M, &C)> {
+        self.entries.iter()
+    }
+
+    /// The members present in either set.
+    ///
+    /// This is a query over the current members, unlike `merge` the
+    /// causal history is not taken into account.
+    pub fn union(&self, other: &Self) -> HashSet<M> {
+        self.iter().chain(other.iter()).cloned().collect()
+    }
+
+    /// The members present in both sets, see `union`.
+    pub fn intersection(&self, other: &Self) -> HashSet<M> {
+        self.iter()
+            .filter(|member| other.entries.contains_key(member))
+            .cloned()
+            .collect()
+    }
+
+    /// The members present in this set but not in `other`, see `union`.
+    pub fn difference(&self, other: &Self) -> HashSet<M> {
+        self.iter()
+            .filter(|member| !other.entries.contains_key(member))
+            .cloned()
+            .collect()
+    }
+
+    /// The current members whose clock is covered by `clock`, i.e. those
+    /// that have not been added, or re-added, since that causal point.
+    ///
+    /// Members removed since `clock` aren't known anymore and are not
+    /// included.
+    pub fn members_as_of(&self, clock: &C) -> HashSet<M> {
+        self.entries
+            .iter()
+            .filter(|(_, member_clock)| *member_clock <= clock)
+            .map(|(member, _)| member.clone())
+            .collect()
+    }
+
+    /// The highest counter of `actor` this set has seen, 0 if it has seen
+    /// none, without cloning the set clock.
+    pub fn actor_counter(&self, actor: &A) -> u64 {
+        self.clock.get(actor)
+    }
+
+    /// Borrow the clock witnessing a member, `None` if the member is absent.
+    ///
+    /// Unlike `contains`, this does not clone the member clock and it does not
+    /// conflate an absent member with an empty clock.
+    ///
+    /// The clock holds a dot for every add of the member that is still
+    /// witnessed. Concurrent adds are merged into a single entry, so dots from
+    /// more than one actor tell that the member was added concurrently.
+    pub fn context_ref(&self, member: &M) -> Option<&C> {
+        self.entries.get(member)
+    }
+
+    /// Iterate, in order, over the members that fall within `range`.
+    ///
+    /// Members are stored in a hash map, so this scans every member and
+    /// sorts the matches, but it avoids cloning the whole set as `read` does.
+    pub fn range<R: RangeBounds<M>>(&self, range: R) -> impl Iterator<Item = &M>
+    where
+        M: Ord,
+    {
+        let mut members: Vec<&M> = self
+            .entries
+            .keys()
+            .filter(|member| range.contains(member))
+            .collect();
+        members.sort_unstable();
+        members.into_iter()
+    }
+
+    /// Check the invariants of this set, returning
+    /// `Error::InconsistentState` if any is violated.
+    ///
+    /// Every member clock must be non-empty and covered by the set clock,
+    /// and every deferred remove must have a non-empty clock and members.
+    pub fn validate(&self) -> Result<()> {
+        let entries_ok = self
+            .entries
+            .values()
+            .all(|clock| !clock.is_empty() && *clock <= self.clock);
+        let deferred_ok = self
+            .deferred
+            .iter()
+            .all(|(clock, members)| !clock.is_empty() && !members.is_empty());
+
+        if entries_ok && deferred_ok {
+            Ok(())
+        } else {
+            Err(Error::InconsistentState)
+        }
+    }
+
+    /// Drop the members whose clock is empty.
+    ///
+    /// Applying ops and merging never leave such members behind, they
+    /// can only come from a state built elsewhere, e.g. deserialized.
+    pub fn gc(&mut self) {
+        self.entries.retain(|_, clock| !clock.is_empty());
+    }
+
+    /// Restore the invariants checked by `validate`, e.g. after loading a
+    /// corrupted state.
+    ///
+    /// The set clock is extended to cover every member clock, so dots are
+    /// never forgotten, and empty member clocks and deferred removes are
+    /// dropped.
+    pub fn repair(&mut self) {
+        self.gc();
+        for clock in self.entries.values() {
+            self.clock.merge(clock.clone());
+        }
+        self.deferred
+            .retain(|clock, members| !clock.is_empty() && !members.is_empty());
+    }
+
+    /// Deserialize a set and `validate` it, meant to be used with
+    /// `#[serde(deserialize_with = "Orswot::deserialize_checked")]` or called
+    /// directly on untrusted, e.g. persisted, data.
+    pub fn deserialize_checked<'de, D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        Self: Deserialize<'de>,
+    {
+        let set = Self::deserialize(deserializer)?;
+        set.validate().map_err(de::Error::custom)?;
+        Ok(set)
+    }
+
+    /// The changes from `previous` to this set as an RFC 6902 JSON Patch.
+    ///
+    /// The patch targets a JSON model of the form `{"members": {<key>: <member>}}`
+    /// where a member's key is its JSON representation, used as is for strings.
+    /// Removes come before adds, each ordered by key.
+    ///
+    /// Fails if a member can't be represented as JSON, e.g. a map with
+    /// non-string keys.
+    #[cfg(feature = "json")]
+    pub fn json_patch_since(&self, previous: &Self) -> serde_json::Result<serde_json::Value>
+    where
+        M: Serialize,
+    {
+        fn path<M: Serialize>(member: &M) -> serde_json::Result<(String, serde_json::Value)> {
+            let value = serde_json::to_value(member)?;
+            let key = match &value {
+                serde_json::Value::String(key) => key.clone(),
+                other => other.to_string(),
+            };
+            let pointer = format!("/members/{}", key.replace('~', "~0").replace('/', "~1"));
+            Ok((pointer, value))
+        }
+
+        let mut removed: Vec<_> = previous
+            .entries
+            .keys()
+            .filter(|member| !self.entries.contains_key(member))
+            .map(path)
+            .collect::<serde_json::Result<_>>()?;
+        let mut added: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|member| !previous.entries.contains_key(member))
+            .map(path)
+            .collect::<serde_json::Result<_>>()?;
+        removed.sort_by(|a, b| a.0.cmp(&b.0));
+        added.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let removes = removed
             .into_iter()
-            .filter_map(|(entry, mut clock)| {
-                if !other.entries.contains_key(&entry) {
-                    // other doesn't contain this entry because it:
-                    //  1. has seen it and dropped it
-                    //  2. hasn't seen it
-                    if other.clock >= clock {
-                        // other has seen this entry and dropped it
-                        None
-                    } else {
-                        // the other map has not seen this version of this
-                        // entry, so add it. But first, we have to remove any
-                        // information that may have been known at some point
-                        // by the other map about this key and was removed.
-                        clock.forget(&other.clock);
-                        Some((entry, clock))
-                    }
+            .map(|(path, _)| serde_json::json!({ "op": "remove", "path": path }));
+        let adds = added
+            .into_iter()
+            .map(|(path, value)| serde_json::json!({ "op": "add", "path": path, "value": value }));
+        Ok(serde_json::Value::Array(removes.chain(adds).collect()))
+    }
+
+    /// Apply an op, but refuse an `Op::Add` whose dot is more than one past
+    /// the set clock for its actor, returning `Error::CausalGap` and leaving
+    /// the set untouched.
+    ///
+    /// This helps diagnose lossy transports, with reliable delivery `apply`
+    /// should be used. Removes are never refused, a remove from the future
+    /// is deferred as usual.
+    pub fn apply_checking_gaps(&mut self, op: Op<M, A, C>) -> Result<()> {
+        if let Op::Add { dot, .. } = &op {
+            if dot.counter > self.clock.get(&dot.actor) + 1 {
+                return Err(Error::CausalGap);
+            }
+        }
+        self.apply(op);
+        Ok(())
+    }
+
+    /// Apply the remove of a single member, returning true if the member
+    /// was present and is now gone.
+    ///
+    /// A remove that only trims the member clock, or is deferred because
+    /// `clock` hasn't been seen yet, returns false, e.g. so a caller can skip
+    /// broadcasting a remove that had no visible effect.
+    pub fn apply_rm_member(&mut self, member: &M, clock: &C) -> bool {
+        let was_present = self.entries.contains_key(member);
+        let members = Some(member.clone()).into_iter().collect();
+        self.apply_rm(members, clock.clone());
+        was_present && !self.entries.contains_key(member)
+    }
+
+    /// Rebuild a set from an op log, applying the ops in order.
+    ///
+    /// Counters may be skipped, e.g. in the log of a set nested in a `Map`
+    /// or in the ops of `diff`, use `apply_checking_gaps` to detect a log
+    /// that is missing entries.
+    pub fn from_ops<I: IntoIterator<Item = Op<M, A, C>>>(ops: I) -> Self {
+        let mut set = Self::default();
+        set.apply_all(ops);
+        set
+    }
+
+    /// Merge many sets into one, equivalent to merging them one at a time
+    /// into an empty set.
+    ///
+    /// The sets are consumed, so nothing is cloned along the way. The result
+    /// is the same regardless of the order of `sets`, pending removes
+    /// included. Equal sets may still serialize to different bytes as hash
+    /// maps are serialized in iteration order, use `serialize_canonical` for
+    /// output that is byte-identical too.
+    pub fn merge_all<I: IntoIterator<Item = Self>>(sets: I) -> Self {
+        let mut merged = Self::default();
+        for set in sets {
+            merged.merge(set);
+        }
+        merged
+    }
+
+    /// Rebuild this set with each member translated by `f`, keeping every
+    /// clock, e.g. when migrating to a new member representation.
+    ///
+    /// The mapping must be injective, i.e. distinct members must map to
+    /// distinct new members, otherwise the clocks of colliding members are
+    /// mixed up and replicas will not converge.
+    pub fn map_members<N: Member, F: Fn(&M) -> N>(&self, f: F) -> Orswot<N, A, C> {
+        Orswot {
+            clock: self.clock.clone(),
+            entries: self
+                .entries
+                .iter()
+                .map(|(member, clock)| (f(member), clock.clone()))
+                .collect(),
+            deferred: self
+                .deferred
+                .iter()
+                .map(|(clock, members)| (clock.clone(), members.iter().map(&f).collect()))
+                .collect(),
+            actor: PhantomData,
+        }
+    }
+
+    /// Merge a peer whose members are encoded with a different type,
+    /// `to_local` translates the peer's members into our member type.
+    ///
+    /// The mapping must be injective, as for `map_members`, otherwise
+    /// replicas will not converge.
+    pub fn merge_mapped<N: Member, F: Fn(&N) -> M>(
+        &mut self,
+        other: &Orswot<N, A, C>,
+        to_local: F,
+    ) {
+        self.merge(other.map_members(to_local));
+    }
+
+    /// Merge another `Orswot` into this one, reporting progress as a fraction
+    /// of the work done. `progress` is called with increasing values, the last
+    /// call is always with `1.0`. The result is the same as `merge`.
+    pub fn merge_with_progress(&mut self, other: Self, progress: &mut dyn FnMut(f32)) {
+        self.merge_inner(other, progress);
+    }
+
+    /// The body of `merge` and `merge_with_progress`, generic over `progress`
+    /// so that `merge`'s no-op callback is inlined away.
+    fn merge_inner<F: FnMut(f32)>(&mut self, mut other: Self, mut progress: F) {
+        // merge is symmetric, so keep the larger set in place and fold the
+        // smaller one into it, this is the common case when merging deltas
+        if self.entries.len() < other.entries.len() {
+            mem::swap(self, &mut other);
+        }
+
+        // one step per entry and deferred remove, and a final step for the clock
+        let steps = (self.entries.len() + other.entries.len() + other.deferred.len() + 1) as f32;
+        let mut done = 0;
+        let mut step = || {
+            done += 1;
+            progress(done as f32 / steps);
+        };
+
+        self.entries.retain(|entry, clock| {
+            step();
+            if !other.entries.contains_key(entry) {
+                // other doesn't contain this entry because it:
+                //  1. has seen it and dropped it
+                //  2. hasn't seen it
+                if other.clock >= *clock {
+                    // other has seen this entry and dropped it
+                    false
                 } else {
-                    Some((entry, clock))
+                    // the other map has not seen this version of this
+                    // entry, so add it. But first, we have to remove any
+                    // information that may have been known at some point
+                    // by the other map about this key and was removed.
+                    clock.forget_context(&other.clock);
+                    true
                 }
-            })
-            .collect();
+            } else {
+                true
+            }
+        });
 
         for (entry, mut clock) in other.entries {
+            step();
             if let Some(our_clock) = self.entries.get_mut(&entry) {
                 // SUBTLE: this entry is present in both orswots, BUT that doesn't mean we
                 // shouldn't drop it!
                 // Perfectly possible that an item in both sets should be dropped
-                let mut common = VClock::intersection(&clock, &our_clock);
-                common.merge(clock.clone_without(&self.clock));
-                common.merge(our_clock.clone_without(&other.clock));
+                let mut common = C::intersection(&clock, our_clock);
+                // both clocks are about to be replaced by `common`, so they
+                // can be trimmed in place rather than cloned
+                clock.forget_context(&self.clock);
+                our_clock.forget_context(&other.clock);
+                common.merge(clock);
+                common.merge(mem::take(our_clock));
                 if common.is_empty() {
                     // both maps had seen each others entry and removed them
                     self.entries.remove(&entry).unwrap();
@@ -129,7 +917,7 @@ impl<M: Member, A: Actor> CvRDT for Orswot<M, A> {
                     // We have not seen this version of this entry, so we add it.
                     // but first, we have to remove the information on this entry
                     // that we have seen and deleted
-                    clock.forget(&self.clock);
+                    clock.forget_context(&self.clock);
                     self.entries.insert(entry, clock);
                 }
             }
@@ -137,25 +925,327 @@ impl<M: Member, A: Actor> CvRDT for Orswot<M, A> {
 
         // merge deferred removals
         for (rm_clock, members) in other.deferred {
+            step();
             self.apply_rm(members, rm_clock);
         }
 
         self.clock.merge(other.clock);
 
         self.apply_deferred();
+        step();
+    }
+
+    /// Drop the deferred removes whose clock is covered by the set clock.
+    ///
+    /// A remove is applied to the members it witnesses as soon as it arrives,
+    /// it's only kept around until the set clock catches up with it. `apply`
+    /// and `merge` prune as they go, this is for sets whose state was built
+    /// elsewhere, e.g. deserialized. Any covered remove is (re)applied before
+    /// being dropped, so members it witnesses don't survive the pruning.
+    pub fn prune_deferred(&mut self) {
+        self.apply_deferred();
+    }
+
+    /// Remove a member using a witnessing clock.
+    fn apply_rm(&mut self, members: HashSet<M>, clock: C) {
+        for member in members.iter() {
+            if let Some(member_clock) = self.entries.get_mut(member) {
+                member_clock.forget_context(&clock);
+                if member_clock.is_empty() {
+                    self.entries.remove(member);
+                }
+            }
+        }
+
+        match clock.partial_cmp(&self.clock) {
+            None | Some(Ordering::Greater) => {
+                if let Some(existing_deferred) = self.deferred.get_mut(&clock) {
+                    existing_deferred.extend(members);
+                } else {
+                    self.deferred.insert(clock, members);
+                }
+            }
+            _ => { /* we've already seen this remove */ }
+        }
+    }
+
+    fn apply_deferred(&mut self) {
+        let deferred = mem::take(&mut self.deferred);
+        for (clock, entries) in deferred.into_iter() {
+            self.apply_rm(entries, clock)
+        }
     }
 }
 
-impl<M: Member, A: Actor> Causal<A> for Orswot<M, A> {
-    fn forget(&mut self, clock: &VClock<A>) {
-        self.clock.forget(&clock);
+/// Collects members into a set, all added by `A::default()`.
+///
+/// Like `Orswot::from_value` this uses a single actor, replicas built this
+/// way share that actor and their dots collide, so don't use the result to
+/// seed more than one replica that will be edited concurrently.
+impl<M: Member, A: Actor + Default> FromIterator<M> for Orswot<M, A> {
+    fn from_iter<I: IntoIterator<Item = M>>(iter: I) -> Self {
+        Orswot::from_value(iter, A::default())
+    }
+}
 
-        self.entries = self
+/// Applies each op in turn, see `CmRDT::apply_all`.
+impl<M: Member, A: Actor, C: CausalContext<A>> Extend<Op<M, A, C>> for Orswot<M, A, C> {
+    fn extend<I: IntoIterator<Item = Op<M, A, C>>>(&mut self, ops: I) {
+        self.apply_all(ops);
+    }
+}
+
+impl<'a, M: Member, A: Actor, C: CausalContext<A>> IntoIterator for &'a Orswot<M, A, C> {
+    type Item = &'a M;
+    type IntoIter = hash_map::Keys<'a, M, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Serializes pre-sorted pairs as a map, see `Orswot::serialize_canonical`.
+struct SortedMap<K, V>(Vec<(K, V)>);
+
+impl<K: Serialize, V: Serialize> Serialize for SortedMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+/// `OrswotDelta` is the part of an `Orswot` that a peer is missing, see
+/// `Orswot::delta`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrswotDelta<M: Member, A: Actor> {
+    /// The clock of the sending set
+    pub clock: VClock<A>,
+    /// The members of the sending set, along with their clocks
+    pub entries: HashMap<M, VClock<A>>,
+    /// The deferred removes of the sending set
+    pub deferred: HashMap<VClock<A>, HashSet<M>>,
+}
+
+/// An effect performed on an `Orswot`, as captured by an `EffectLog`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect<M: Member, A: Actor> {
+    /// An op was applied
+    Apply(Op<M, A>),
+    /// The state of a peer was merged in
+    Merge(Orswot<M, A>),
+}
+
+/// `EffectLog` captures the sequence of ops applied and merges performed
+/// on an `Orswot` so that it can be replayed deterministically, e.g. to
+/// turn a convergence bug seen in the field into a regression test.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectLog<M: Member, A: Actor> {
+    /// The recorded effects, in the order they were performed
+    pub effects: Vec<Effect<M, A>>,
+}
+
+impl<M: Member, A: Actor> Default for EffectLog<M, A> {
+    fn default() -> Self {
+        EffectLog {
+            effects: Vec::new(),
+        }
+    }
+}
+
+impl<M: Member, A: Actor> EffectLog<M, A> {
+    /// Returns an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an op that was applied
+    pub fn record_apply(&mut self, op: Op<M, A>) {
+        self.effects.push(Effect::Apply(op));
+    }
+
+    /// Record a merge with the given peer state
+    pub fn record_merge(&mut self, peer: Orswot<M, A>) {
+        self.effects.push(Effect::Merge(peer));
+    }
+
+    /// Replay the recorded effects, in order, against an empty `Orswot`.
+    pub fn replay(&self) -> Orswot<M, A> {
+        let mut set = Orswot::new();
+        for effect in self.effects.iter() {
+            match effect {
+                Effect::Apply(op) => set.apply(op.clone()),
+                Effect::Merge(peer) => set.merge(peer.clone()),
+            }
+        }
+        set
+    }
+}
+
+/// `IndexedOrswot` wraps an `Orswot` and keeps a secondary index of its
+/// members, grouped by `key_fn`, in sync as the set is mutated.
+///
+/// The index is a local projection and is not part of the CRDT state.
+pub struct IndexedOrswot<M: Member + Ord, A: Actor, K: Ord, I: Fn(&M) -> K> {
+    set: Orswot<M, A>,
+    index: BTreeMap<K, BTreeSet<M>>,
+    key_fn: I,
+    empty: BTreeSet<M>,
+}
+
+impl<M: Member + Ord, A: Actor, K: Ord, I: Fn(&M) -> K> IndexedOrswot<M, A, K, I> {
+    /// Returns an empty set indexed by `key_fn`
+    pub fn new(key_fn: I) -> Self {
+        IndexedOrswot {
+            set: Orswot::new(),
+            index: BTreeMap::new(),
+            key_fn,
+            empty: BTreeSet::new(),
+        }
+    }
+
+    /// Borrow the underlying set, e.g. to derive contexts for new ops.
+    pub fn set(&self) -> &Orswot<M, A> {
+        &self.set
+    }
+
+    /// The live members whose key is `key`.
+    pub fn by_key(&self, key: &K) -> &BTreeSet<M> {
+        self.index.get(key).unwrap_or(&self.empty)
+    }
+
+    /// Apply an op to the underlying set and update the index.
+    pub fn apply(&mut self, op: Op<M, A>) {
+        // an add may unblock any deferred remove, so those members
+        // have to be revisited as well
+        let mut touched: Vec<M> = self.set.deferred.values().flatten().cloned().collect();
+        match &op {
+            Op::Add { member, .. } => touched.push(member.clone()),
+            Op::Rm { members, .. } => touched.extend(members.iter().cloned()),
+        }
+
+        self.set.apply(op);
+        for member in touched {
+            self.reindex(member);
+        }
+    }
+
+    /// Merge a peer's state into the underlying set and rebuild the index.
+    pub fn merge(&mut self, other: Orswot<M, A>) {
+        self.set.merge(other);
+
+        self.index.clear();
+        for member in self.set.entries.keys() {
+            self.index
+                .entry((self.key_fn)(member))
+                .or_default()
+                .insert(member.clone());
+        }
+    }
+
+    fn reindex(&mut self, member: M) {
+        let key = (self.key_fn)(&member);
+        if self.set.entries.contains_key(&member) {
+            self.index.entry(key).or_default().insert(member);
+        } else if let Some(members) = self.index.get_mut(&key) {
+            members.remove(&member);
+            if members.is_empty() {
+                self.index.remove(&key);
+            }
+        }
+    }
+}
+
+/// `LRUOrswot` wraps an `Orswot` and bounds the number of members, evicting
+/// the oldest members once the cap is exceeded.
+///
+/// The age of a member is the highest counter among its dots, ties are
+/// broken by member order. Evictions are decided from CRDT state alone, so
+/// replicas with the same state evict the same members. Concurrent adds on
+/// different replicas can push a merged set over the cap, call `evict`
+/// after merging to bring it back down.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LRUOrswot<M: Member + Ord, A: Actor> {
+    set: Orswot<M, A>,
+    cap: usize,
+}
+
+impl<M: Member + Ord, A: Actor> LRUOrswot<M, A> {
+    /// Returns an empty set holding at most `cap` members.
+    pub fn new(cap: usize) -> Self {
+        LRUOrswot {
+            set: Orswot::new(),
+            cap,
+        }
+    }
+
+    /// Borrow the underlying set, e.g. to derive contexts for new ops.
+    pub fn set(&self) -> &Orswot<M, A> {
+        &self.set
+    }
+
+    /// Add a member, followed by the removes of any members evicted by it.
+    pub fn add(&self, member: M, ctx: AddCtx<A>) -> Vec<Op<M, A>> {
+        let add = self.set.add(member, ctx);
+        let mut next = self.set.clone();
+        next.apply(add.clone());
+
+        let mut ops = vec![add];
+        ops.extend(Self::evictions(&next, self.cap));
+        ops
+    }
+
+    /// The removes needed to bring the set back down to its cap.
+    pub fn evict(&self) -> Vec<Op<M, A>> {
+        Self::evictions(&self.set, self.cap)
+    }
+
+    /// Apply an op to the underlying set.
+    pub fn apply(&mut self, op: Op<M, A>) {
+        self.set.apply(op);
+    }
+
+    /// Merge a peer's state into the underlying set.
+    pub fn merge(&mut self, other: Self) {
+        self.set.merge(other.set);
+    }
+
+    fn evictions(set: &Orswot<M, A>, cap: usize) -> Vec<Op<M, A>> {
+        if set.entries.len() <= cap {
+            return Vec::new();
+        }
+
+        let age = |clock: &VClock<A>| clock.iter().map(|dot| dot.counter).max().unwrap_or(0);
+        let mut by_age: Vec<(u64, &M)> = set
+            .entries
+            .iter()
+            .map(|(member, clock)| (age(clock), member))
+            .collect();
+        by_age.sort();
+
+        by_age
+            .into_iter()
+            .take(set.entries.len() - cap)
+            .map(|(_, member)| set.rm_member(member))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+    extern crate rand;
+
+    /// The previous `forget`, which rebuilt the entries and deferred maps
+    /// from clones.
+    fn forget_by_rebuilding(set: &mut Orswot<u8, u8>, clock: &VClock<u8>) {
+        set.clock.forget(clock);
+
+        set.entries = set
             .entries
             .clone()
             .into_iter()
             .filter_map(|(val, mut val_clock)| {
-                val_clock.forget(&clock);
+                val_clock.forget(clock);
                 if val_clock.is_empty() {
                     None
                 } else {
@@ -164,12 +1254,12 @@ impl<M: Member, A: Actor> Causal<A> for Orswot<M, A> {
             })
             .collect();
 
-        self.deferred = self
+        set.deferred = set
             .deferred
             .clone()
             .into_iter()
             .filter_map(|(mut vclock, deferred)| {
-                vclock.forget(&clock);
+                vclock.forget(clock);
                 if vclock.is_empty() {
                     None
                 } else {
@@ -178,91 +1268,143 @@ impl<M: Member, A: Actor> Causal<A> for Orswot<M, A> {
             })
             .collect();
     }
-}
 
-impl<M: Member, A: Actor> Orswot<M, A> {
-    /// Returns a new `Orswot` instance.
-    pub fn new() -> Self {
-        Orswot {
-            clock: VClock::new(),
-            entries: HashMap::new(),
-            deferred: HashMap::new(),
-        }
+    /// A reference model of a replica, the add dots and removes it has seen.
+    /// A member is live while one of its add dots isn't covered by a remove
+    /// of that member.
+    #[derive(Debug, Clone, Default)]
+    struct Model {
+        adds: BTreeSet<(u8, Dot<u8>)>,
+        rms: Vec<(u8, VClock<u8>)>,
     }
 
-    /// Add a single element.
-    pub fn add(&self, member: M, ctx: AddCtx<A>) -> Op<M, A> {
-        Op::Add {
-            dot: ctx.dot,
-            member,
+    impl Model {
+        fn live(&self) -> HashSet<u8> {
+            self.adds
+                .iter()
+                .filter(|(member, dot)| {
+                    !self
+                        .rms
+                        .iter()
+                        .any(|(rm, clock)| rm == member && clock.get(&dot.actor) >= dot.counter)
+                })
+                .map(|(member, _)| *member)
+                .collect()
         }
-    }
 
-    /// Remove a member with a witnessing ctx.
-    pub fn rm(&self, member: M, ctx: RmCtx<A>) -> Op<M, A> {
-        let mut members = HashSet::new();
-        members.insert(member);
-        Op::Rm {
-            clock: ctx.clock,
-            members,
+        fn merge(&mut self, other: &Model) {
+            self.adds.extend(other.adds.iter().cloned());
+            self.rms.extend(other.rms.iter().cloned());
         }
     }
 
-    /// Remove a member using a witnessing clock.
-    fn apply_rm(&mut self, members: HashSet<M>, clock: VClock<A>) {
-        for member in members.iter() {
-            if let Some(member_clock) = self.entries.get_mut(&member) {
-                member_clock.forget(&clock);
-                if member_clock.is_empty() {
-                    self.entries.remove(&member);
+    quickcheck! {
+        fn prop_forget_in_place_matches_rebuilding(
+            ops: Vec<(u8, u8, bool, u8)>,
+            forget_dots: Vec<(u8, u8)>
+        ) -> bool {
+            let mut set: Orswot<u8, u8> = Orswot::new();
+            for (actor, member, is_add, counter) in ops {
+                let dot = Dot::new(actor % 4, u64::from(counter % 8));
+                if is_add {
+                    set.apply(Op::Add { dot, member });
+                } else {
+                    set.apply(Op::Rm {
+                        clock: dot.into(),
+                        members: vec![member].into_iter().collect(),
+                    });
                 }
             }
+            let clock: VClock<u8> = forget_dots
+                .into_iter()
+                .map(|(actor, counter)| Dot::new(actor % 4, u64::from(counter % 8)))
+                .collect();
+
+            let mut expected = set.clone();
+            forget_by_rebuilding(&mut expected, &clock);
+            set.forget(&clock);
+
+            // a rebuild overwrote deferred removes whose clocks collided,
+            // forgetting in place keeps all of their members
+            set.clock == expected.clock
+                && set.entries == expected.entries
+                && expected
+                    .deferred
+                    .iter()
+                    .all(|(clock, members)| set.deferred[clock].is_superset(members))
+                && set.deferred.len() == expected.deferred.len()
         }
 
-        match clock.partial_cmp(&self.clock) {
-            None | Some(Ordering::Greater) => {
-                if let Some(existing_deferred) = self.deferred.get_mut(&clock) {
-                    existing_deferred.extend(members);
-                } else {
-                    self.deferred.insert(clock, members);
+        fn prop_ops_and_merges_leave_no_tombstones(ops: Vec<(u8, u8, u8, u8)>) -> bool {
+            let mut replicas: Vec<Orswot<u8, u8>> = vec![Orswot::new(); 3];
+            let mut models: Vec<Model> = vec![Model::default(); 3];
+            for (replica, member, kind, other) in ops {
+                let actor = replica % 3;
+                let i = actor as usize;
+                let member = member % 8;
+                let set = &replicas[i];
+                match kind % 4 {
+                    0 => {
+                        let op = set.add(member, set.read().derive_add_ctx(actor));
+                        if let Op::Add { dot, .. } = &op {
+                            models[i].adds.insert((member, dot.clone()));
+                        }
+                        replicas[i].apply(op);
+                    }
+                    1 => {
+                        let op = set.rm_member(&member);
+                        if let Op::Rm { clock, .. } = &op {
+                            models[i].rms.push((member, clock.clone()));
+                        }
+                        replicas[i].apply(op);
+                    }
+                    2 => {
+                        // a remove from the future, it is deferred
+                        let witness = other % 3;
+                        let counter = set.clock.get(&witness) + 1 + u64::from(other % 2);
+                        let clock: VClock<u8> = Dot::new(witness, counter).into();
+                        models[i].rms.push((member, clock.clone()));
+                        replicas[i].apply(Op::Rm {
+                            clock,
+                            members: vec![member].into_iter().collect(),
+                        });
+                    }
+                    _ => {
+                        let peer = (other % 3) as usize;
+                        let peer_set = replicas[peer].clone();
+                        let peer_model = models[peer].clone();
+                        replicas[i].merge(peer_set);
+                        models[i].merge(&peer_model);
+                    }
                 }
-            }
-            _ => { /* we've already seen this remove */ }
-        }
-    }
 
-    /// Check if the set contains a member
-    pub fn contains(&self, member: &M) -> ReadCtx<bool, A> {
-        let member_clock_opt = self.entries.get(&member);
-        let exists = member_clock_opt.is_some();
-        ReadCtx {
-            add_clock: self.clock.clone(),
-            rm_clock: member_clock_opt.cloned().unwrap_or_default(),
-            val: exists,
-        }
-    }
+                let tombstone_free = replicas.iter().zip(&models).all(|(set, model)| {
+                    set.entries.values().all(|clock| !clock.is_empty())
+                        && set.validate().is_ok()
+                        && set.entries.len() <= model.live().len()
+                        && set.read().val == model.live()
+                });
+                if !tombstone_free {
+                    return false;
+                }
+            }
 
-    /// Retrieve the current members.
-    pub fn read(&self) -> ReadCtx<HashSet<M>, A> {
-        ReadCtx {
-            add_clock: self.clock.clone(),
-            rm_clock: self.clock.clone(),
-            val: self.entries.keys().cloned().collect(),
-        }
-    }
+            let mut merged = Orswot::new();
+            let mut model = Model::default();
+            for (set, replica_model) in replicas.into_iter().zip(&models) {
+                merged.merge(set);
+                model.merge(replica_model);
+            }
+            if merged.entries.len() > model.live().len() {
+                return false;
+            }
 
-    fn apply_deferred(&mut self) {
-        let deferred = mem::replace(&mut self.deferred, HashMap::new());
-        for (clock, entries) in deferred.into_iter() {
-            self.apply_rm(entries, clock)
+            // removing everything observed leaves no entries behind
+            let op = merged.clear(merged.read().derive_rm_ctx());
+            merged.apply(op);
+            merged.entries.is_empty()
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    extern crate rand;
 
     #[test]
     // a bug found with rust quickcheck where deferred operations
@@ -333,6 +1475,26 @@ mod tests {
         assert!(a.read().val.is_empty());
     }
 
+    #[test]
+    fn test_redelivered_rm_does_not_grow_deferred() {
+        let mut a: Orswot<u8, &str> = Orswot::new();
+        let op = a.rm(
+            1,
+            RmCtx {
+                clock: Dot::new("A", 3).into(),
+            },
+        );
+
+        a.apply(op.clone());
+        assert_eq!(a.deferred.len(), 1);
+        let once = a.clone();
+
+        a.apply(op);
+        assert_eq!(a, once);
+        assert_eq!(a.deferred.len(), 1);
+        assert_eq!(a.deferred[&Dot::new("A", 3).into()].len(), 1);
+    }
+
     // port from riak_dt
     // Bug found by EQC, not dropping dots in merge when an element is
     // present in both Sets leads to removed items remaining after merge.
@@ -365,4 +1527,72 @@ mod tests {
         a.merge(c);
         assert!(a.read().val.is_empty());
     }
+
+    #[test]
+    fn test_prune_deferred_drops_covered_removes() {
+        let mut a: Orswot<u8, &str> = Orswot::new();
+        a.apply(a.add(1, a.read().derive_add_ctx("A")));
+        a.apply(a.rm(
+            1,
+            RmCtx {
+                clock: Dot::new("B", 1).into(),
+            },
+        ));
+        a.apply(a.rm(
+            2,
+            RmCtx {
+                clock: Dot::new("B", 2).into(),
+            },
+        ));
+        assert_eq!(a.deferred.len(), 2);
+
+        // a state where the clock has moved past a deferred remove, as could
+        // be loaded from storage
+        a.clock.apply(Dot::new("B", 1));
+        let members = a.read().val;
+
+        a.prune_deferred();
+        assert_eq!(a.deferred.len(), 1);
+        assert_eq!(a.read().val, members);
+    }
+
+    #[test]
+    fn test_repair_restores_invariants() {
+        let mut a: Orswot<u8, &str> = Orswot::new();
+        a.apply(a.add(1, a.read().derive_add_ctx("A")));
+        a.apply(a.add(2, a.read().derive_add_ctx("A")));
+        assert_eq!(a.validate(), Ok(()));
+
+        // member 2 holds a dot the set clock has never seen
+        a.entries.get_mut(&2).unwrap().apply(Dot::new("B", 4));
+        a.entries.insert(3, VClock::new());
+        a.deferred.insert(Dot::new("C", 1).into(), HashSet::new());
+        assert_eq!(a.validate(), Err(Error::InconsistentState));
+
+        a.repair();
+        assert_eq!(a.validate(), Ok(()));
+        assert_eq!(a.read().val, vec![1, 2].into_iter().collect());
+        assert_eq!(
+            a.clock,
+            vec![Dot::new("A", 2), Dot::new("B", 4)]
+                .into_iter()
+                .collect()
+        );
+        assert!(a.deferred.is_empty());
+    }
+
+    #[test]
+    fn test_gc_drops_empty_entries() {
+        let mut a: Orswot<u8, &str> = Orswot::new();
+        a.apply(a.add(1, a.read().derive_add_ctx("A")));
+        a.apply(a.add(2, a.read().derive_add_ctx("A")));
+        let clean = a.clone();
+
+        a.entries.insert(3, VClock::new());
+        assert!(a.contains(&3).val);
+
+        a.gc();
+        assert_eq!(a, clean);
+        assert_eq!(a.read().val, vec![1, 2].into_iter().collect());
+    }
 }
@@ -1,7 +1,10 @@
+use std::hash::{Hash, Hasher};
+
 use num_bigint::BigInt;
 use serde::{Deserialize, Serialize};
 
 use crate::gcounter::GCounter;
+use crate::map::{Divergence, Fingerprint};
 use crate::traits::{Causal, CmRDT, CvRDT};
 use crate::vclock::{Actor, Dot, VClock};
 
@@ -74,10 +77,18 @@ impl<A: Actor> CvRDT for PNCounter<A> {
     }
 }
 
+impl<A: Actor> Divergence for PNCounter<A> {}
+
 impl<A: Actor> Causal<A> for PNCounter<A> {
     fn forget(&mut self, clock: &VClock<A>) {
-        self.p.forget(&clock);
-        self.n.forget(&clock);
+        self.p.forget(clock);
+        self.n.forget(clock);
+    }
+}
+
+impl<A: Actor> Fingerprint for PNCounter<A> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        self.hash(state);
     }
 }
 
@@ -182,4 +193,24 @@ mod test {
         a.apply(a.inc("A"));
         assert_eq!(a.read(), 2.into());
     }
+
+    #[test]
+    fn test_concurrent_inc_and_dec_converge() {
+        let mut a = PNCounter::new();
+        let mut b = PNCounter::new();
+
+        a.apply(a.inc("A"));
+        a.apply(a.inc("A"));
+        b.apply(b.dec("B"));
+        b.apply(b.dec("B"));
+        b.apply(b.dec("B"));
+
+        let mut ab = a.clone();
+        ab.merge(b.clone());
+        let mut ba = b;
+        ba.merge(a);
+
+        assert_eq!(ab, ba);
+        assert_eq!(ab.read(), (-1).into());
+    }
 }
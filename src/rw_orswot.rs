@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ctx::{AddCtx, ReadCtx};
+use crate::orswot::Member;
+use crate::traits::{CmRDT, CvRDT};
+use crate::vclock::{Actor, Dot, VClock};
+
+/// `RWOrswot` is a remove-biased (remove-wins) or-set, a remove suppresses
+/// every add of the member that it wasn't witnessed by, concurrent adds
+/// included.
+///
+/// This is a different CRDT from the add-biased `Orswot` and the two are not
+/// interchangeable, replicas must agree on which one they run. Where the
+/// `Orswot` forgets removed members, this set keeps a clock of the removes
+/// of every member it has seen (a tombstone) since a concurrent add may
+/// arrive at any time, so its state grows with every member ever removed.
+///
+/// Each add records the removes of its member that it has seen, a member is
+/// present while one of its adds has seen every remove of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RWOrswot<M: Member, A: Actor> {
+    clock: VClock<A>,
+    adds: HashMap<M, BTreeMap<Dot<A>, VClock<A>>>,
+    rms: HashMap<M, VClock<A>>,
+}
+
+/// Op's define an edit to an `RWOrswot`, unlike an `Orswot` both adds and
+/// removes are witnessed by a dot of their own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<M: Member, A: Actor> {
+    /// Add a member to the set
+    Add {
+        /// witnessing dot
+        dot: Dot<A>,
+        /// Member to add
+        member: M,
+        /// The removes of the member seen by the add
+        seen_rms: VClock<A>,
+    },
+    /// Remove a member from the set
+    Rm {
+        /// witnessing dot
+        dot: Dot<A>,
+        /// Member to remove
+        member: M,
+    },
+}
+
+impl<M: Member, A: Actor> Default for RWOrswot<M, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Member, A: Actor> CmRDT for RWOrswot<M, A> {
+    type Op = Op<M, A>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            Op::Add {
+                dot,
+                member,
+                seen_rms,
+            } => {
+                self.clock.apply(dot.clone());
+                let adds = self.adds.entry(member.clone()).or_default();
+                adds.insert(dot, seen_rms);
+                self.prune(&member);
+            }
+            Op::Rm { dot, member } => {
+                self.clock.apply(dot.clone());
+                self.rms.entry(member.clone()).or_default().apply(dot);
+                self.prune(&member);
+            }
+        }
+    }
+}
+
+impl<M: Member, A: Actor> CvRDT for RWOrswot<M, A> {
+    /// Merge is the union of the adds and removes of both sets, the adds
+    /// suppressed by a remove from either side are dropped.
+    fn merge(&mut self, other: Self) {
+        self.clock.merge(other.clock);
+        for (member, rms) in other.rms {
+            self.rms.entry(member).or_default().merge(rms);
+        }
+        for (member, adds) in other.adds {
+            self.adds.entry(member).or_default().extend(adds);
+        }
+
+        let rms = &self.rms;
+        self.adds.retain(|member, adds| {
+            adds.retain(|_, seen_rms| saw_all_rms(seen_rms, rms.get(member)));
+            !adds.is_empty()
+        });
+    }
+}
+
+impl<M: Member, A: Actor> RWOrswot<M, A> {
+    /// Returns a new `RWOrswot` instance.
+    pub fn new() -> Self {
+        RWOrswot {
+            clock: VClock::new(),
+            adds: HashMap::new(),
+            rms: HashMap::new(),
+        }
+    }
+
+    /// Add a single member, the add survives the removes of the member this
+    /// set has seen but not concurrent ones.
+    pub fn add(&self, member: M, ctx: AddCtx<A>) -> Op<M, A> {
+        let seen_rms = self.rms.get(&member).cloned().unwrap_or_default();
+        Op::Add {
+            dot: ctx.dot,
+            member,
+            seen_rms,
+        }
+    }
+
+    /// Remove a member, suppressing every add of it that hasn't seen this
+    /// remove.
+    ///
+    /// A remove has a dot of its own, so it takes an `AddCtx`.
+    pub fn rm(&self, member: M, ctx: AddCtx<A>) -> Op<M, A> {
+        Op::Rm {
+            dot: ctx.dot,
+            member,
+        }
+    }
+
+    /// Check if the set contains a member
+    pub fn contains(&self, member: &M) -> ReadCtx<bool, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.adds.contains_key(member),
+        }
+    }
+
+    /// Gets all members in the set
+    pub fn read(&self) -> ReadCtx<HashSet<M>, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.adds.keys().cloned().collect(),
+        }
+    }
+
+    /// Drop the adds of a member that are suppressed by one of its removes.
+    fn prune(&mut self, member: &M) {
+        let rms = self.rms.get(member);
+        if let Some(adds) = self.adds.get_mut(member) {
+            adds.retain(|_, seen_rms| saw_all_rms(seen_rms, rms));
+            if adds.is_empty() {
+                self.adds.remove(member);
+            }
+        }
+    }
+}
+
+/// An add survives if it has seen every remove of its member.
+fn saw_all_rms<A: Actor>(seen_rms: &VClock<A>, rms: Option<&VClock<A>>) -> bool {
+    rms.is_none_or(|rms| seen_rms >= rms)
+}
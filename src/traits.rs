@@ -1,11 +1,22 @@
 use std::fmt::Debug;
+use std::hash::Hash;
 
-use crate::vclock::{Actor, VClock};
+use crate::vclock::{Actor, Dot, VClock};
 
 /// State based CRDT's replicate by transmitting the entire CRDT state.
 pub trait CvRDT {
     /// Merge the given CRDT into the current CRDT.
     fn merge(&mut self, other: Self);
+
+    /// Return the merge of this CRDT and `other`, leaving both untouched.
+    fn merged(&self, other: &Self) -> Self
+    where
+        Self: Clone,
+    {
+        let mut merged = self.clone();
+        merged.merge(other.clone());
+        merged
+    }
 }
 
 /// Operation based CRDT's replicate by transmitting each operation.
@@ -35,6 +46,30 @@ pub trait CmRDT {
 
     /// Apply an Op to the CRDT
     fn apply(&mut self, op: Self::Op);
+
+    /// Apply a batch of Op's in order, e.g. when replaying an op log.
+    fn apply_all<I: IntoIterator<Item = Self::Op>>(&mut self, ops: I)
+    where
+        Self: Sized,
+    {
+        for op in ops {
+            self.apply(op);
+        }
+    }
+
+    /// Apply an Op, returning true if it changed the CRDT and false if it
+    /// was a no-op, e.g. an Op that was already applied.
+    ///
+    /// The default compares the state before and after applying the Op,
+    /// CRDT's that can tell up front should override it.
+    fn apply_changed(&mut self, op: Self::Op) -> bool
+    where
+        Self: Clone + PartialEq,
+    {
+        let before = self.clone();
+        self.apply(op);
+        *self != before
+    }
 }
 
 /// CRDT's are causal if they are built on top of vector clocks.
@@ -43,6 +78,35 @@ pub trait Causal<A: Actor> {
     fn forget(&mut self, clock: &VClock<A>);
 }
 
+/// The causal context CRDT's like the `Orswot` use to track causality.
+///
+/// `VClock` is the default implementation, other contexts (e.g. a dotted
+/// version vector) can be plugged in by implementing this trait.
+pub trait CausalContext<A: Actor>:
+    Debug + Clone + Default + Eq + Hash + PartialOrd + Causal<A> + CmRDT<Op = Dot<A>> + CvRDT
+{
+    /// Return the associated counter for this actor.
+    /// All actors not in the context have an implied count of 0
+    fn get(&self, actor: &A) -> u64;
+
+    /// Returns `true` if this context contains nothing.
+    fn is_empty(&self) -> bool;
+
+    /// Returns the common elements of two contexts.
+    fn intersection(left: &Self, right: &Self) -> Self;
+
+    /// Forget any information that is dominated by the given context.
+    fn forget_context(&mut self, other: &Self);
+
+    /// Returns a clone of self but with information that is dominated by the
+    /// given context forgotten.
+    fn clone_without(&self, base: &Self) -> Self {
+        let mut cloned = self.clone();
+        cloned.forget_context(base);
+        cloned
+    }
+}
+
 /// Funky variant of the `CvRDT` trait.
 ///
 /// This trait is for CvRDT's whose state space can't be easily encoded in rusts
@@ -70,4 +134,15 @@ pub trait FunkyCmRDT {
 
     /// Apply an Op to the CRDT
     fn apply(&mut self, op: Self::Op) -> Result<(), Self::Error>;
+
+    /// Apply a batch of Op's in order, stopping at the first error.
+    fn apply_all<I: IntoIterator<Item = Self::Op>>(&mut self, ops: I) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        for op in ops {
+            self.apply(op)?;
+        }
+        Ok(())
+    }
 }
@@ -15,13 +15,18 @@
 // TODO: we have a mixture of language here with witness and actor. Clean this up
 use std::cmp::{self, Ordering};
 use std::collections::{btree_map, BTreeMap};
+use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::mem;
 
+#[cfg(feature = "compact-ops")]
+use serde::{de, Deserializer, Serializer};
 use serde::{Deserialize, Serialize};
 
-use crate::traits::{Causal, CmRDT, CvRDT};
+use crate::error::Error;
+use crate::map::{Divergence, Fingerprint};
+use crate::traits::{Causal, CausalContext, CmRDT, CvRDT};
 
 /// Common Actor type. Actors are unique identifier for every `thing` mutating a VClock.
 /// VClock based CRDT's will need to expose this Actor type to the user.
@@ -29,7 +34,9 @@ pub trait Actor: Ord + Clone + Hash + Debug {}
 impl<A: Ord + Clone + Hash + Debug> Actor for A {}
 
 /// Dot is a version marker for a single actor
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Dots are ordered by actor and then by counter.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Dot<A> {
     /// The actor identifier
     pub actor: A,
@@ -42,6 +49,40 @@ impl<A: Actor> Dot<A> {
     pub fn new(actor: A, counter: u64) -> Self {
         Self { actor, counter }
     }
+
+    /// The next version of this actor, i.e. this dot with its counter
+    /// incremented
+    pub fn increment(self) -> Self {
+        Self {
+            actor: self.actor,
+            counter: self.counter + 1,
+        }
+    }
+}
+
+impl<A: Actor> From<(A, u64)> for Dot<A> {
+    fn from((actor, counter): (A, u64)) -> Self {
+        Self::new(actor, counter)
+    }
+}
+
+impl<A: Display> Display for Dot<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.actor, self.counter)
+    }
+}
+
+/// How two `VClock`'s relate causally, see `VClock::compare`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VClockOrdering {
+    /// Both clocks have seen the same versions
+    Equal,
+    /// The clock has seen strictly less than the other
+    Less,
+    /// The clock has seen strictly more than the other
+    Greater,
+    /// Each clock has seen versions the other has not
+    Concurrent,
 }
 
 /// A `VClock` is a standard vector clock.
@@ -53,6 +94,9 @@ impl<A: Actor> Dot<A> {
 /// It can tell you if something causally descends something else,
 /// or if different replicas are "concurrent" (were mutated in
 /// isolation, and need to be resolved externally).
+///
+/// Actors that are absent from a `VClock` have an implied count of 0, so a
+/// `VClock` never stores a zero counter.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VClock<A: Actor> {
     /// dots is the mapping from actors to their associated counters
@@ -82,11 +126,11 @@ impl<A: Actor> PartialOrd for VClock<A> {
 impl<A: Actor + Display> Display for VClock<A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "<")?;
-        for (i, (actor, count)) in self.dots.iter().enumerate() {
+        for (i, dot) in self.iter().enumerate() {
             if i > 0 {
                 write!(f, ", ")?;
             }
-            write!(f, "{}:{}", actor, count)?;
+            write!(f, "{}", dot)?;
         }
         write!(f, ">")
     }
@@ -97,8 +141,8 @@ impl<A: Actor> Causal<A> for VClock<A> {
     /// count in the given vclock
     fn forget(&mut self, other: &Self) {
         for Dot { actor, counter } in other.iter() {
-            if counter >= self.get(&actor) {
-                self.dots.remove(&actor);
+            if counter >= self.get(actor) {
+                self.dots.remove(actor);
             }
         }
     }
@@ -135,6 +179,36 @@ impl<A: Actor> CvRDT for VClock<A> {
     }
 }
 
+impl<A: Actor> Divergence for VClock<A> {}
+
+impl<A: Actor> CausalContext<A> for VClock<A> {
+    fn get(&self, actor: &A) -> u64 {
+        VClock::get(self, actor)
+    }
+
+    fn is_empty(&self) -> bool {
+        VClock::is_empty(self)
+    }
+
+    fn intersection(left: &Self, right: &Self) -> Self {
+        VClock::intersection(left, right)
+    }
+
+    fn forget_context(&mut self, other: &Self) {
+        self.forget(other);
+    }
+
+    fn clone_without(&self, base: &Self) -> Self {
+        VClock::clone_without(self, base)
+    }
+}
+
+impl<A: Actor> Fingerprint for VClock<A> {
+    fn hash_resolved<H: Hasher>(&self, state: &mut H) {
+        self.hash(state);
+    }
+}
+
 impl<A: Actor> VClock<A> {
     /// Returns a new `VClock` instance.
     pub fn new() -> Self {
@@ -145,12 +219,47 @@ impl<A: Actor> VClock<A> {
 
     /// Returns a clone of self but with information that is older than given clock is
     /// forgotten
+    ///
+    /// This is the reset-remove primitive: the result keeps only the dots
+    /// of `self` that `base_clock` has not seen. Resetting by a dominating
+    /// clock gives an empty clock, resetting by a concurrent clock keeps
+    /// the dots that are new to it.
+    /// ``` rust
+    /// use crdts::{VClock, Dot};
+    /// let clock: VClock<_> = vec![Dot::new("A", 2), Dot::new("B", 1)].into_iter().collect();
+    /// let seen: VClock<_> = Dot::new("A", 2).into();
+    ///
+    /// assert_eq!(clock.clone_without(&clock), VClock::new());
+    /// assert_eq!(clock.clone_without(&seen), Dot::new("B", 1).into());
+    /// ```
     pub fn clone_without(&self, base_clock: &Self) -> Self {
         let mut cloned = self.clone();
-        cloned.forget(&base_clock);
+        cloned.forget(base_clock);
         cloned
     }
 
+    /// Forget the actors whose counters are covered by `clock`, as `forget`
+    /// does, returning the dots that were dropped.
+    ///
+    /// The dropped dots together with the remaining ones make up the original
+    /// clock again.
+    pub fn truncate_to(&mut self, clock: &Self) -> Self {
+        let (dots, kept) = mem::take(&mut self.dots)
+            .into_iter()
+            .partition(|(actor, counter)| clock.get(actor) >= *counter);
+        self.dots = kept;
+        Self { dots }
+    }
+
+    /// Move the counter of actor `from` onto actor `to`, e.g. when a
+    /// replica's actor id changes. If `to` already has a counter, the
+    /// greater of the two is kept.
+    pub fn rename_actor(&mut self, from: &A, to: A) {
+        if let Some(counter) = self.dots.remove(from) {
+            self.apply_dot(Dot::new(to, counter));
+        }
+    }
+
     /// Apply a Dot to this vclock.
     fn apply_dot(&mut self, dot: Dot<A>) {
         if self.get(&dot.actor) < dot.counter {
@@ -202,12 +311,41 @@ impl<A: Actor> VClock<A> {
         self.partial_cmp(other).is_none()
     }
 
+    /// Compare two vector clocks, like `partial_cmp` but with concurrency
+    /// spelled out.
+    ///
+    /// # Examples
+    /// ```
+    /// use crdts::{Dot, VClock};
+    /// use crdts::vclock::VClockOrdering;
+    /// let a: VClock<_> = Dot::new("A", 1).into();
+    /// let b: VClock<_> = Dot::new("B", 1).into();
+    /// assert_eq!(a.compare(&b), VClockOrdering::Concurrent);
+    /// assert_eq!(a.compare(&a.lub(&b)), VClockOrdering::Less);
+    /// ```
+    pub fn compare(&self, other: &VClock<A>) -> VClockOrdering {
+        match self.partial_cmp(other) {
+            Some(Ordering::Equal) => VClockOrdering::Equal,
+            Some(Ordering::Less) => VClockOrdering::Less,
+            Some(Ordering::Greater) => VClockOrdering::Greater,
+            None => VClockOrdering::Concurrent,
+        }
+    }
+
     /// Return the associated counter for this actor.
     /// All actors not in the vclock have an implied count of 0
     pub fn get(&self, actor: &A) -> u64 {
         self.dots.get(actor).cloned().unwrap_or(0)
     }
 
+    /// Return the current dot for this actor, i.e. the last version of the
+    /// actor this clock has seen, with a counter of 0 if it has none.
+    ///
+    /// Use `inc` for the next dot of an actor.
+    pub fn dot(&self, actor: &A) -> Dot<A> {
+        Dot::new(actor.clone(), self.get(actor))
+    }
+
     /// Returns `true` if this vector clock contains nothing.
     pub fn is_empty(&self) -> bool {
         self.dots.is_empty()
@@ -244,7 +382,7 @@ impl<A: Actor> VClock<A> {
     /// assert_eq!(c.get(&43), 0);
     /// ```
     pub fn glb(&mut self, other: &Self) {
-        self.dots = mem::replace(&mut self.dots, BTreeMap::new())
+        self.dots = mem::take(&mut self.dots)
             .into_iter()
             .filter_map(|(actor, count)| {
                 // Since an actor missing from the dots map has an implied
@@ -258,6 +396,27 @@ impl<A: Actor> VClock<A> {
             .collect();
     }
 
+    /// Returns the least-upper-bound of the given VClock and itself, i.e.
+    /// the per-actor maximum, without mutating either.
+    /// ``` rust
+    /// use crdts::{VClock, Dot};
+    /// let a: VClock<_> = vec![Dot::new("A", 3), Dot::new("B", 2)].into_iter().collect();
+    /// let b: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 5)].into_iter().collect();
+    ///
+    /// let lub = a.lub(&b);
+    /// assert_eq!(lub.get(&"A"), 3);
+    /// assert_eq!(lub.get(&"B"), 5);
+    /// ```
+    pub fn lub(&self, other: &Self) -> Self {
+        let mut lub = self.clone();
+        for (actor, counter) in other.dots.iter() {
+            if lub.get(actor) < *counter {
+                lub.dots.insert(actor.clone(), *counter);
+            }
+        }
+        lub
+    }
+
     /// Returns an iterator over the dots in this vclock
     pub fn iter(&self) -> impl Iterator<Item = Dot<&A>> {
         self.dots.iter().map(|(a, c)| Dot {
@@ -265,6 +424,11 @@ impl<A: Actor> VClock<A> {
             counter: *c,
         })
     }
+
+    /// Returns an iterator over the actors in this vclock, in order
+    pub fn actors(&self) -> impl Iterator<Item = &A> {
+        self.dots.keys()
+    }
 }
 
 /// Generated from calls to VClock::into_iter()
@@ -306,6 +470,20 @@ impl<A: Actor> std::iter::FromIterator<Dot<A>> for VClock<A> {
     }
 }
 
+/// Folds the clocks into their least upper bound, e.g. to compute the
+/// frontier of many replicas.
+impl<A: Actor> std::iter::FromIterator<VClock<A>> for VClock<A> {
+    fn from_iter<I: IntoIterator<Item = VClock<A>>>(iter: I) -> Self {
+        let mut clock = VClock::new();
+
+        for other in iter {
+            clock.merge(other);
+        }
+
+        clock
+    }
+}
+
 impl<A: Actor> From<Dot<A>> for VClock<A> {
     fn from(dot: Dot<A>) -> Self {
         let mut clock = VClock::new();
@@ -313,3 +491,99 @@ impl<A: Actor> From<Dot<A>> for VClock<A> {
         clock
     }
 }
+
+impl<A: Actor> TryFrom<BTreeMap<A, u64>> for VClock<A> {
+    type Error = Error;
+
+    /// Build a `VClock` from a map of actor counters, a zero counter is
+    /// rejected with `Error::ZeroCounter` since absent actors are implied to
+    /// be at 0.
+    fn try_from(dots: BTreeMap<A, u64>) -> Result<Self, Self::Error> {
+        if dots.values().any(|counter| *counter == 0) {
+            Err(Error::ZeroCounter)
+        } else {
+            Ok(Self { dots })
+        }
+    }
+}
+
+impl<A: Actor> TryFrom<Vec<(A, u64)>> for VClock<A> {
+    type Error = Error;
+
+    /// Build a `VClock` from (actor, counter) pairs, a repeated actor is
+    /// rejected with `Error::DuplicateActor` and a zero counter as with
+    /// `TryFrom<BTreeMap<A, u64>>`.
+    fn try_from(pairs: Vec<(A, u64)>) -> Result<Self, Self::Error> {
+        let len = pairs.len();
+        let dots: BTreeMap<A, u64> = pairs.into_iter().collect();
+        if dots.len() == len {
+            VClock::try_from(dots)
+        } else {
+            Err(Error::DuplicateActor)
+        }
+    }
+}
+
+/// A causal context with a compact wire form, used for the context of
+/// remove op's with the `compact-ops` feature, see `compact`.
+#[cfg(feature = "compact-ops")]
+pub trait CompactSerialize {
+    /// Serialize this context in its compact form
+    fn serialize_compact<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+/// The decoding side of `CompactSerialize`.
+#[cfg(feature = "compact-ops")]
+pub trait CompactDeserialize<'de>: Sized {
+    /// Deserialize a context from its compact form
+    fn deserialize_compact<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// A `VClock` is compacted to a list of `(actor, counter)` pairs sorted by
+/// actor.
+#[cfg(feature = "compact-ops")]
+impl<A: Actor + Serialize> CompactSerialize for VClock<A> {
+    fn serialize_compact<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.dots.iter())
+    }
+}
+
+#[cfg(feature = "compact-ops")]
+impl<'de, A: Actor + Deserialize<'de>> CompactDeserialize<'de> for VClock<A> {
+    /// The pairs must be sorted by actor with no repeated actor or zero
+    /// counter, as `serialize_compact` produces them.
+    fn deserialize_compact<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs: Vec<(A, u64)> = Vec::deserialize(deserializer)?;
+        if pairs.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(de::Error::custom("clock actors must be sorted and unique"));
+        }
+        VClock::try_from(pairs.into_iter().collect::<BTreeMap<_, _>>()).map_err(de::Error::custom)
+    }
+}
+
+/// Serde helpers for `#[serde(with = "crate::vclock::compact")]`, encoding a
+/// context in its `CompactSerialize` form.
+///
+/// Only op's are encoded this way, the state of a CRDT keeps the derived
+/// encoding whether the feature is enabled or not.
+#[cfg(feature = "compact-ops")]
+pub mod compact {
+    use serde::{Deserializer, Serializer};
+
+    use super::{CompactDeserialize, CompactSerialize};
+
+    /// Serialize a context in its compact form
+    pub fn serialize<C: CompactSerialize, S: Serializer>(
+        clock: &C,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        clock.serialize_compact(serializer)
+    }
+
+    /// Deserialize a context from its compact form
+    pub fn deserialize<'de, C: CompactDeserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<C, D::Error> {
+        C::deserialize_compact(deserializer)
+    }
+}
@@ -0,0 +1,47 @@
+use crdts::*;
+
+#[test]
+fn test_set_and_get_meta() {
+    let mut set: AnnotatedOrswot<u8, &str, u8> = AnnotatedOrswot::new();
+    let op = set.set_meta(1, "one", set.get_meta(&1).derive_add_ctx(1));
+    set.apply(op);
+    assert!(set.contains(&1).val);
+    assert_eq!(set.get_meta(&1).val, Some("one"));
+
+    let op = set.add(2, set.contains(&2).derive_add_ctx(1));
+    set.apply(op);
+    assert_eq!(set.get_meta(&2).val, None);
+
+    let op = set.rm(1, set.contains(&1).derive_rm_ctx());
+    set.apply(op);
+    assert!(!set.contains(&1).val);
+    assert_eq!(set.get_meta(&1).val, None);
+
+    // re-adding a removed member does not bring back its old metadata
+    let op = set.add(1, set.contains(&1).derive_add_ctx(1));
+    set.apply(op);
+    assert_eq!(set.get_meta(&1).val, None);
+}
+
+#[test]
+fn test_concurrent_meta_update_and_remove_converge() {
+    let mut a: AnnotatedOrswot<u8, &str, u8> = AnnotatedOrswot::new();
+    let op = a.set_meta(1, "old", a.get_meta(&1).derive_add_ctx(1));
+    a.apply(op);
+    let mut b = a.clone();
+
+    let set_meta = a.set_meta(1, "new", a.get_meta(&1).derive_add_ctx(1));
+    a.apply(set_meta.clone());
+    let rm = b.rm(1, b.contains(&1).derive_rm_ctx());
+    b.apply(rm.clone());
+
+    // one replica exchanges ops, the other merges state
+    let mut c = a.clone();
+    c.apply(rm);
+    b.apply(set_meta);
+    a.merge(b.clone());
+
+    assert_eq!(a, b);
+    assert_eq!(a, c);
+    assert_eq!(a.get_meta(&1).val, Some("new"));
+}
@@ -1,4 +1,5 @@
-use crdts::{map, mvreg, Causal, CmRDT, CvRDT, Dot, MVReg, Map, VClock};
+use crdts::map::Divergence;
+use crdts::{map, mvreg, Causal, CmRDT, CvRDT, Dot, MVReg, Map, Orswot, VClock};
 use quickcheck::TestResult;
 
 use super::vclock;
@@ -8,8 +9,9 @@ type TKey = u8;
 type TVal = MVReg<u8, TActor>;
 type TOp = map::Op<TKey, Map<TKey, TVal, TActor>, TActor>;
 type TMap = Map<TKey, Map<TKey, TVal, TActor>, TActor>;
+type TOpPrims = (u8, Vec<(u8, u8, u8, u8, u8)>);
 
-fn build_ops(prims: (u8, Vec<(u8, u8, u8, u8, u8)>)) -> (TActor, Vec<TOp>) {
+fn build_ops(prims: TOpPrims) -> (TActor, Vec<TOp>) {
     let (actor, ops_data) = prims;
 
     let mut ops = Vec::new();
@@ -361,6 +363,7 @@ fn test_commute_quickcheck_bug() {
 }
 
 #[test]
+#[allow(array_into_iter, clippy::into_iter_on_ref)]
 fn test_idempotent_quickcheck_bug1() {
     let ops = vec![
         map::Op::Up {
@@ -515,6 +518,315 @@ fn test_idempotent_quickcheck1() {
     assert_eq!(m, m_snapshot);
 }
 
+#[test]
+fn test_snapshot() {
+    let mut m: Map<u8, Orswot<u8, &str>, &str> = Map::new();
+    m.apply(m.update(1, m.get(&1).derive_add_ctx("A"), |set, ctx| {
+        set.add(10, ctx)
+    }));
+    m.apply(m.update(2, m.get(&2).derive_add_ctx("A"), |set, ctx| {
+        set.add(20, ctx)
+    }));
+    m.apply(m.rm(2, m.get(&2).derive_rm_ctx()));
+
+    let snapshot = m.snapshot();
+    assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec![&1]);
+    assert_eq!(snapshot[&1].read().val, vec![10].into_iter().collect());
+}
+
+#[test]
+fn test_fingerprint_converges_across_merge_orders() {
+    let mut a: Map<u8, Orswot<u8, &str>, &str> = Map::new();
+    let mut b = a.clone();
+    let mut c = a.clone();
+    a.apply(a.update(1, a.get(&1).derive_add_ctx("A"), |set, ctx| {
+        set.add(10, ctx)
+    }));
+    b.apply(b.update(2, b.get(&2).derive_add_ctx("B"), |set, ctx| {
+        set.add(20, ctx)
+    }));
+    c.apply(c.update(1, c.get(&1).derive_add_ctx("C"), |set, ctx| {
+        set.add(30, ctx)
+    }));
+
+    assert_ne!(a.fingerprint(), b.fingerprint());
+
+    let mut abc = a.clone();
+    abc.merge(b.clone());
+    abc.merge(c.clone());
+
+    let mut cba = c;
+    cba.merge(b);
+    cba.merge(a);
+
+    assert_eq!(abc, cba);
+    assert_eq!(abc.fingerprint(), cba.fingerprint());
+}
+
+#[test]
+fn test_find_divergence_points_at_nested_value() {
+    let mut a: TMap = Map::new();
+    for inner_key in &[2, 3] {
+        let op = a.update(1, a.get(&1).derive_add_ctx(1), |inner, ctx| {
+            inner.update(*inner_key, ctx, |reg, ctx| reg.write(0, ctx))
+        });
+        a.apply(op);
+    }
+    let mut b = a.clone();
+    assert_eq!(a.find_divergence(&b), None);
+
+    let op = b.update(1, b.get(&1).derive_add_ctx(2), |inner, ctx| {
+        inner.update(2, ctx, |reg, ctx| reg.write(1, ctx))
+    });
+    b.apply(op);
+
+    assert_eq!(
+        a.find_divergence(&b),
+        Some(vec![vec!["1".to_string(), "2".to_string()]])
+    );
+}
+
+#[test]
+fn test_merge_with_reports_reset_remove() {
+    let mut m1 = TMap::new();
+
+    m1.apply(m1.update(101, m1.get(&101).derive_add_ctx(74), |map, ctx| {
+        map.update(110, ctx, |reg, ctx| reg.write(32, ctx))
+    }));
+    m1.apply(m1.update(102, m1.get(&102).derive_add_ctx(74), |map, ctx| {
+        map.update(110, ctx, |reg, ctx| reg.write(1, ctx))
+    }));
+
+    let mut m2 = m1.clone();
+
+    m1.apply(m1.rm(101, m1.get(&101).derive_rm_ctx()));
+    m1.apply(m1.rm(102, m1.get(&102).derive_rm_ctx()));
+
+    m2.apply(m2.update(101, m2.get(&101).derive_add_ctx(37), |map, ctx| {
+        map.update(220, ctx, |reg, ctx| reg.write(5, ctx))
+    }));
+    m2.apply(m2.update(103, m2.get(&103).derive_add_ctx(37), |map, ctx| {
+        map.update(220, ctx, |reg, ctx| reg.write(6, ctx))
+    }));
+
+    let mut events = Vec::new();
+    let mut merged = m1.clone();
+    merged.merge_with(m2.clone(), |key, event| events.push((*key, event)));
+    events.sort_by_key(|(key, _)| *key);
+    assert_eq!(
+        events,
+        vec![
+            (101, map::MergeEvent::Reset),
+            (102, map::MergeEvent::Dropped),
+            (103, map::MergeEvent::Added),
+        ]
+    );
+
+    let mut events = Vec::new();
+    let mut plain = m2.clone();
+    m2.merge_with(m1.clone(), |key, event| events.push((*key, event)));
+    events.sort_by_key(|(key, _)| *key);
+    assert_eq!(
+        events,
+        vec![
+            (101, map::MergeEvent::Reset),
+            (102, map::MergeEvent::Dropped),
+        ]
+    );
+
+    // the callback doesn't change the outcome of the merge
+    plain.merge(m1);
+    assert_eq!(m2, plain);
+    assert_eq!(m2, merged);
+}
+
+#[test]
+fn test_merge_with_reports_deferred_remove() {
+    let mut a = TMap::new();
+    a.apply(a.update(1, a.get(&1).derive_add_ctx(1), |map, ctx| {
+        map.update(110, ctx, |reg, ctx| reg.write(1, ctx))
+    }));
+    a.apply(a.update(2, a.get(&2).derive_add_ctx(1), |map, ctx| {
+        map.update(110, ctx, |reg, ctx| reg.write(2, ctx))
+    }));
+    a.apply(a.update(2, a.get(&2).derive_add_ctx(2), |map, ctx| {
+        map.update(220, ctx, |reg, ctx| reg.write(3, ctx))
+    }));
+
+    // b has not seen the entries the remove was made under, it defers it
+    let mut b = TMap::new();
+    b.apply(TOp::Rm {
+        clock: Dot::new(1, 2).into(),
+        keyset: vec![1, 2].into_iter().collect(),
+    });
+
+    let mut events = Vec::new();
+    let mut merged = a.clone();
+    merged.merge_with(b.clone(), |key, event| events.push((*key, event)));
+    assert_eq!(
+        events,
+        vec![(1, map::MergeEvent::Dropped), (2, map::MergeEvent::Reset)]
+    );
+
+    a.merge(b);
+    assert_eq!(merged, a);
+    assert_eq!(a.len().val, 1);
+}
+
+#[test]
+fn test_forget_resets_nested_values() {
+    let mut m1 = TMap::new();
+    m1.apply(m1.update(1, m1.get(&1).derive_add_ctx(7), |map, ctx| {
+        map.update(10, ctx, |reg, ctx| reg.write(1, ctx))
+    }));
+    m1.apply(m1.update(2, m1.get(&2).derive_add_ctx(7), |map, ctx| {
+        map.update(20, ctx, |reg, ctx| reg.write(2, ctx))
+    }));
+    let compaction_point = m1.len().rm_clock;
+
+    let mut m2 = m1.clone();
+    m2.apply(m2.update(2, m2.get(&2).derive_add_ctx(8), |map, ctx| {
+        map.update(21, ctx, |reg, ctx| reg.write(3, ctx))
+    }));
+    m1.merge(m2);
+
+    // the edits seen by the compaction point are dropped in one call,
+    // the entry only edited before it goes away
+    let mut compacted = m1.clone();
+    compacted.forget(&compaction_point);
+    assert_eq!(compacted.len().val, 1);
+    let inner = compacted.get(&2).val.unwrap();
+    assert_eq!(inner.get(&20).val, None);
+    assert_eq!(inner.get(&21).val.map(|r| r.read().val), Some(vec![3]));
+
+    compacted.forget(&m1.len().rm_clock);
+    assert_eq!(compacted, TMap::new());
+}
+
+#[test]
+fn test_keys_after_reset_remove() {
+    let mut m1 = TMap::new();
+    for key in [101, 102] {
+        m1.apply(m1.update(key, m1.get(&key).derive_add_ctx(74), |map, ctx| {
+            map.update(110, ctx, |reg, ctx| reg.write(32, ctx))
+        }));
+    }
+
+    let mut m2 = m1.clone();
+
+    m1.apply(m1.rm(101, m1.get(&101).derive_rm_ctx()));
+    m1.apply(m1.rm(102, m1.get(&102).derive_rm_ctx()));
+
+    m2.apply(m2.update(101, m2.get(&101).derive_add_ctx(37), |map, ctx| {
+        map.update(220, ctx, |reg, ctx| reg.write(5, ctx))
+    }));
+
+    m1.merge(m2);
+
+    // 102 was removed, 101 survives the remove through the concurrent edit
+    assert_eq!(m1.keys().collect::<Vec<_>>(), vec![&101]);
+    assert_eq!(m1.len().val, 1);
+    assert!(!m1.is_empty().val);
+
+    let inner = m1.get(&101).val.unwrap();
+    assert_eq!(inner.keys().collect::<Vec<_>>(), vec![&220]);
+}
+
+#[test]
+fn test_contains_key() {
+    let mut m: Map<u8, Orswot<u8, u8>, u8> = Map::new();
+    m.apply(m.update(1, m.get(&1).derive_add_ctx(1), |set, ctx| set.add(10, ctx)));
+    m.apply(m.update(2, m.get(&2).derive_add_ctx(1), |set, ctx| set.add(20, ctx)));
+
+    assert!(m.contains_key(&1).val);
+    assert!(m.contains_key(&2).val);
+    assert!(!m.contains_key(&3).val);
+    assert_eq!(m.contains_key(&1).rm_clock, m.get(&1).rm_clock);
+
+    m.apply(m.rm(2, m.contains_key(&2).derive_rm_ctx()));
+    assert!(m.contains_key(&1).val);
+    assert!(!m.contains_key(&2).val);
+    assert_eq!(m.contains_key(&2).rm_clock, VClock::new());
+}
+
+#[test]
+fn test_update_all() {
+    let mut m1 = TMap::new();
+    m1.apply(m1.update(1, m1.get(&1).derive_add_ctx(7), |map, ctx| {
+        map.update(10, ctx, |reg, ctx| reg.write(1, ctx))
+    }));
+    let mut m2 = m1.clone();
+
+    let ops = m1.update_all(vec![1, 2, 3], m1.len().derive_add_ctx(7), |map, ctx| {
+        map.update(20, ctx, |reg, ctx| reg.write(2, ctx))
+    });
+    assert_eq!(ops.len(), 3);
+    m1.apply_all(ops.clone());
+    m2.apply_all(ops);
+    assert_eq!(m1, m2);
+
+    assert_eq!(m1.keys().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+    for key in 1..=3 {
+        let inner = m1.get(&key).val.unwrap();
+        assert_eq!(inner.get(&20).val.map(|r| r.read().val), Some(vec![2]));
+    }
+    // untouched nested values are kept
+    let inner = m1.get(&1).val.unwrap();
+    assert_eq!(inner.get(&10).val.map(|r| r.read().val), Some(vec![1]));
+}
+
+#[test]
+fn test_get_or_default() {
+    let mut m: Map<u8, Orswot<u8, u8>, u8> = Map::new();
+    m.apply(m.update(1, m.get(&1).derive_add_ctx(1), |set, ctx| set.add(10, ctx)));
+
+    let present = m.get_or_default(&1);
+    assert_eq!(present.val, m.get(&1).val.unwrap());
+    assert_eq!(present.rm_clock, m.get(&1).rm_clock);
+
+    let absent = m.get_or_default(&2);
+    assert_eq!(absent.val, Orswot::new());
+    assert_eq!(absent.add_clock, m.len().add_clock);
+    assert!(absent.rm_clock.is_empty());
+}
+
+#[test]
+fn test_rm_checked() {
+    let mut m1 = TMap::new();
+    m1.apply(m1.update(101, m1.get(&101).derive_add_ctx(74), |map, ctx| {
+        map.update(110, ctx, |reg, ctx| reg.write(32, ctx))
+    }));
+    let mut m2 = m1.clone();
+
+    assert!(m1.rm_checked(102, m1.get(&102).derive_rm_ctx()).is_none());
+
+    let op = m1.rm_checked(101, m1.get(&101).derive_rm_ctx()).unwrap();
+    assert_eq!(op, m1.rm(101, m1.get(&101).derive_rm_ctx()));
+    m1.apply(op);
+    assert!(m1.rm_checked(101, m1.get(&101).derive_rm_ctx()).is_none());
+
+    // a concurrent edit keeps the key through a reset remove
+    m2.apply(m2.update(101, m2.get(&101).derive_add_ctx(37), |map, ctx| {
+        map.update(220, ctx, |reg, ctx| reg.write(5, ctx))
+    }));
+    m1.merge(m2);
+    assert!(m1.rm_checked(101, m1.get(&101).derive_rm_ctx()).is_some());
+}
+
+#[test]
+fn test_fingerprint_covers_nested_values() {
+    let m: Map<u8, MVReg<u8, u8>, u8> = Map::new();
+    let mut a = m.clone();
+    let mut b = m.clone();
+
+    // both replicas reuse the same dot, only the written values differ
+    a.apply(m.update(1, m.get(&1).derive_add_ctx(7), |reg, ctx| reg.write(5, ctx)));
+    b.apply(m.update(1, m.get(&1).derive_add_ctx(7), |reg, ctx| reg.write(6, ctx)));
+
+    assert_eq!(a.len().add_clock, b.len().add_clock);
+    assert_ne!(a.fingerprint(), b.fingerprint());
+}
+
 fn apply_ops(map: &mut TMap, ops: &[TOp]) {
     for op in ops.iter().cloned() {
         map.apply(op);
@@ -524,8 +836,8 @@ fn apply_ops(map: &mut TMap, ops: &[TOp]) {
 quickcheck! {
     // TODO: add test to show equivalence of merge and Op exchange
     fn prop_op_exchange_same_as_merge(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -550,8 +862,8 @@ quickcheck! {
     }
 
     fn prop_op_exchange_converges(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -578,9 +890,9 @@ quickcheck! {
     }
 
     fn prop_op_exchange_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims,
+        ops3_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -611,7 +923,7 @@ quickcheck! {
     }
 
     fn prop_op_idempotent(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpPrims
     ) -> bool {
         let ops = build_ops(ops_prim);
         let mut m = TMap::new();
@@ -624,9 +936,9 @@ quickcheck! {
     }
 
     fn prop_op_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims,
+        ops3_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -658,9 +970,9 @@ quickcheck! {
 
 
     fn prop_merge_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims,
+        ops3_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -692,8 +1004,8 @@ quickcheck! {
     }
 
     fn prop_merge_commutative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -721,8 +1033,8 @@ quickcheck! {
 
 
     fn prop_merge_followed_by_merge(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -748,7 +1060,7 @@ quickcheck! {
     }
 
     fn prop_merge_idempotent(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpPrims
     ) -> bool {
         let ops = build_ops(ops_prim);
 
@@ -764,7 +1076,7 @@ quickcheck! {
     }
 
     fn prop_forget_with_empty_vclock_is_nop(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpPrims
     ) -> bool {
         let ops = build_ops(ops_prim);
 
@@ -778,7 +1090,7 @@ quickcheck! {
     }
 
     fn prop_forget_with_map_clock_is_empty_map(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpPrims
     ) -> bool {
         let mut m = TMap::new();
         apply_ops(&mut m, &build_ops(ops_prim).1);
@@ -792,8 +1104,8 @@ quickcheck! {
     }
 
     fn prop_forget_than_merge_same_as_merge_than_forget(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
+        ops1_prim: TOpPrims,
+        ops2_prim: TOpPrims,
         vclock_prim: Vec<u8>
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
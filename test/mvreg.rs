@@ -82,6 +82,28 @@ fn test_multi_val() {
     assert!(r1.read().val == vec![32, 82] || r1.read().val == vec![82, 32]);
 }
 
+#[test]
+fn test_causally_later_write_collapses_concurrent_values() {
+    let mut r1 = MVReg::new();
+    let mut r2 = MVReg::new();
+
+    r1.apply(r1.write(32, r1.read().derive_add_ctx("A")));
+    r2.apply(r2.write(82, r2.read().derive_add_ctx("B")));
+
+    r1.merge(r2.clone());
+    let mut values = r1.read().val;
+    values.sort();
+    assert_eq!(values, vec![32, 82]);
+
+    // a write that has seen both values replaces them
+    r1.apply(r1.write(7, r1.read().derive_add_ctx("A")));
+    assert_eq!(r1.read().val, vec![7]);
+
+    r2.merge(r1.clone());
+    assert_eq!(r2.read().val, vec![7]);
+    assert_eq!(r2, r1);
+}
+
 #[test]
 fn test_op_commute_quickcheck1() {
     let mut reg1 = MVReg::new();
@@ -115,7 +137,7 @@ fn ops_are_not_compatible(opss: &[&Vec<(u8, u8)>]) -> bool {
                 a_clock.apply(a_clock.inc(*a_actor));
                 b_clock.apply(b_clock.inc(*b_actor));
 
-                if b_clock.get(&a_actor) == a_clock.get(&a_actor) {
+                if b_clock.get(a_actor) == a_clock.get(a_actor) {
                     // this check is a bit broad as it's not a failure
                     // to insert the same value with the same actor version
                     // but for simplicity we reject those ops as well
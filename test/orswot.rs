@@ -2,16 +2,17 @@ extern crate crdts;
 extern crate rand;
 
 use crdts::{orswot::Op, *};
+use std::cmp::Ordering;
 use std::collections::HashSet;
 
-const ACTOR_MAX: u8 = 11;
+use super::testkit;
 
 #[derive(Debug, Clone)]
-struct OpVec {
-    ops: Vec<(u8, Op<u8, u8>)>,
+struct OpVec<C: CausalContext<u8>> {
+    ops: Vec<(u8, Op<u8, u8, C>)>,
 }
 
-fn build_opvec(op_prims: Vec<(u8, u8, u8, u64)>) -> OpVec {
+fn build_opvec<C: CausalContext<u8>>(op_prims: Vec<(u8, u8, u8, u64)>) -> OpVec<C> {
     let mut ops = Vec::new();
     for (actor, member, choice, counter) in op_prims {
         let op = match choice % 2 {
@@ -19,50 +20,90 @@ fn build_opvec(op_prims: Vec<(u8, u8, u8, u64)>) -> OpVec {
                 member,
                 dot: Dot { actor, counter },
             },
-            _ => Op::Rm {
-                members: vec![member].into_iter().collect(),
-                clock: Dot { actor, counter }.into(),
-            },
+            _ => {
+                let mut clock = C::default();
+                clock.apply(Dot { actor, counter });
+                Op::Rm {
+                    members: vec![member].into_iter().collect(),
+                    clock,
+                }
+            }
         };
         ops.push((actor, op));
     }
     OpVec { ops }
 }
 
+/// A trivial alternative causal context, it wraps a `VClock`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct WrappedClock(VClock<u8>);
+
+impl PartialOrd for WrappedClock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl Causal<u8> for WrappedClock {
+    fn forget(&mut self, clock: &VClock<u8>) {
+        self.0.forget(clock)
+    }
+}
+
+impl CmRDT for WrappedClock {
+    type Op = Dot<u8>;
+
+    fn apply(&mut self, dot: Self::Op) {
+        self.0.apply(dot)
+    }
+}
+
+impl CvRDT for WrappedClock {
+    fn merge(&mut self, other: Self) {
+        self.0.merge(other.0)
+    }
+}
+
+impl CausalContext<u8> for WrappedClock {
+    fn get(&self, actor: &u8) -> u64 {
+        self.0.get(actor)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn intersection(left: &Self, right: &Self) -> Self {
+        WrappedClock(VClock::intersection(&left.0, &right.0))
+    }
+
+    fn forget_context(&mut self, other: &Self) {
+        self.0.forget(&other.0)
+    }
+}
+
 quickcheck! {
     fn prop_merge_converges(op_prims: Vec<(u8, u8, u8, u64)>) -> bool {
-        let ops = build_opvec(op_prims);
-        // Different interleavings of ops applied to different
-        // orswots should all converge when merged. Apply the
-        // ops to increasing numbers of witnessing orswots,
-        // then merge them together and make sure they have
-        // all converged.
-        let mut result = None;
-        for i in 2..ACTOR_MAX {
-            let mut witnesses: Vec<Orswot<u8, u8>> =
-                (0..i).map(|_| Orswot::new()).collect();
-            for op_pair in ops.ops.iter() {
-                let (actor, op) = op_pair;
-                let witness = &mut witnesses[(actor % i) as usize];
-                witness.apply(op.clone());
-            }
-            let mut merged = Orswot::new();
-            for witness in witnesses {
-                merged.merge(witness);
-            }
+        testkit::merge_converges::<Orswot<u8, u8>>(&build_opvec(op_prims).ops)
+    }
 
-            if let Some(ref prev_res) = result {
-                if prev_res != &merged {
-                    println!("opvec: {:?}", ops);
-                    println!("result: {:?}", result);
-                    println!("merged: {:?}", merged);
-                    return false;
-                };
-            } else {
-                result = Some(merged);
-            }
+    fn prop_merge_converges_with_alternative_context(op_prims: Vec<(u8, u8, u8, u64)>) -> bool {
+        testkit::merge_converges::<Orswot<u8, u8, WrappedClock>>(&build_opvec(op_prims).ops)
+    }
+
+    fn prop_merge_all_same_as_sequential_merge(op_prims: Vec<(u8, u8, u8, u64)>) -> bool {
+        let ops = build_opvec::<VClock<u8>>(op_prims);
+        let mut witnesses: Vec<Orswot<u8, u8>> = (0..4).map(|_| Orswot::new()).collect();
+        for (actor, op) in ops.ops {
+            witnesses[(actor % 4) as usize].apply(op);
         }
-        true
+
+        let mut sequential = Orswot::new();
+        for witness in witnesses.iter().cloned() {
+            sequential.merge(witness);
+        }
+
+        Orswot::merge_all(witnesses) == sequential
     }
 }
 
@@ -81,7 +122,6 @@ fn weird_highlight_1() {
     assert!(a.read().val.is_empty());
 }
 
-///
 #[test]
 fn adds_dont_destroy_causality() {
     let mut a = Orswot::new();
@@ -129,7 +169,7 @@ fn merge_clocks_of_identical_entries() {
     let mut final_clock = VClock::new();
     final_clock.apply(final_clock.inc("A"));
     final_clock.apply(final_clock.inc("B"));
-    assert_eq!(a.contains(&1).val, true);
+    assert!(a.contains(&1).val);
     assert_eq!(a.contains(&1).rm_clock, final_clock);
 }
 
@@ -268,3 +308,1148 @@ fn test_reset_remove_semantics() {
         vec![2].into_iter().collect()
     );
 }
+
+#[test]
+fn test_context_ref_borrows_member_clock() {
+    // a member that panics if it is ever cloned
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    struct NoClone(u8);
+
+    impl Clone for NoClone {
+        fn clone(&self) -> Self {
+            panic!("member was cloned");
+        }
+    }
+
+    let mut a = Orswot::new();
+    a.apply(a.add(NoClone(1), a.read().derive_add_ctx("A")));
+
+    assert_eq!(a.context_ref(&NoClone(1)), Some(&Dot::new("A", 1).into()));
+    assert_eq!(a.context_ref(&NoClone(2)), None);
+}
+
+#[test]
+fn test_merge_mapped_across_member_encodings() {
+    let mut a: Orswot<String, &str> = Orswot::new();
+    let mut b: Orswot<u8, &str> = Orswot::new();
+
+    a.apply(a.add("1".to_string(), a.read().derive_add_ctx("A")));
+    b.apply(b.add(2, b.read().derive_add_ctx("B")));
+    b.apply(b.add(3, b.read().derive_add_ctx("B")));
+    b.apply(b.rm(3, b.contains(&3).derive_rm_ctx()));
+
+    a.merge_mapped(&b, |n| n.to_string());
+
+    assert_eq!(
+        a.read().val,
+        vec!["1".to_string(), "2".to_string()].into_iter().collect()
+    );
+    assert_eq!(
+        a.read().add_clock,
+        vec![Dot::new("A", 1), Dot::new("B", 2)]
+            .into_iter()
+            .collect()
+    );
+}
+
+#[test]
+fn test_concurrent_add_wins_over_remove() {
+    let mut a = Orswot::new();
+    a.apply(a.add("element", a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    a.apply(a.rm("element", a.contains(&"element").derive_rm_ctx()));
+    b.apply(b.add("element", b.read().derive_add_ctx("B")));
+
+    let mut ab = a.clone();
+    ab.merge(b.clone());
+    b.merge(a);
+
+    assert_eq!(ab, b);
+    assert_eq!(ab.read().val, vec!["element"].into_iter().collect());
+    assert_eq!(ab.contains(&"element").rm_clock, Dot::new("B", 1).into());
+}
+
+#[test]
+fn test_divergence_score() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let b = a.clone();
+
+    // identical
+    assert_eq!(a.divergence_score(&b.read().add_clock), 0);
+
+    // slightly diverged
+    let mut c = b.clone();
+    c.apply(c.add(2, c.read().derive_add_ctx("C")));
+    assert_eq!(a.divergence_score(&c.read().add_clock), 1);
+
+    // very diverged, in both directions
+    for i in 0..10 {
+        a.apply(a.add(i, a.read().derive_add_ctx("A")));
+        c.apply(c.add(i, c.read().derive_add_ctx("C")));
+    }
+    assert_eq!(a.divergence_score(&c.read().add_clock), 21);
+    assert_eq!(c.divergence_score(&a.read().add_clock), 21);
+}
+
+#[test]
+fn test_effect_log_replays_to_same_state() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let mut b: Orswot<u8, u8> = Orswot::new();
+    let mut log = orswot::EffectLog::new();
+
+    let op = a.add(1, a.read().derive_add_ctx(1));
+    a.apply(op.clone());
+    log.record_apply(op);
+
+    let op = b.add(2, b.read().derive_add_ctx(2));
+    b.apply(op);
+    a.merge(b.clone());
+    log.record_merge(b);
+
+    let op = a.rm(1, a.contains(&1).derive_rm_ctx());
+    a.apply(op.clone());
+    log.record_apply(op);
+
+    assert_eq!(log.replay(), a);
+    assert_eq!(log.replay().read().val, vec![2].into_iter().collect());
+}
+
+#[test]
+fn test_indexed_orswot_tracks_members_by_key() {
+    let mut indexed = orswot::IndexedOrswot::new(|m: &u8| m % 2);
+
+    for member in &[1, 2, 3] {
+        let op = indexed
+            .set()
+            .add(*member, indexed.set().read().derive_add_ctx(1));
+        indexed.apply(op);
+    }
+    assert_eq!(indexed.by_key(&1), &vec![1, 3].into_iter().collect());
+    assert_eq!(indexed.by_key(&0), &vec![2].into_iter().collect());
+
+    let op = indexed
+        .set()
+        .rm(2, indexed.set().contains(&2).derive_rm_ctx());
+    indexed.apply(op);
+    assert!(indexed.by_key(&0).is_empty());
+
+    // a peer that has seen our adds and removed 3
+    let mut peer = indexed.set().clone();
+    let op = peer.rm(3, peer.contains(&3).derive_rm_ctx());
+    peer.apply(op);
+
+    indexed.merge(peer);
+    assert_eq!(indexed.by_key(&1), &vec![1].into_iter().collect());
+}
+
+#[test]
+fn test_op_dependencies() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let first = a.add(1, a.read().derive_add_ctx(1));
+    a.apply(first.clone());
+    let second = a.add(2, a.read().derive_add_ctx(1));
+    a.apply(second.clone());
+    let rm = a.rm(1, a.contains(&1).derive_rm_ctx());
+    a.apply(rm.clone());
+
+    assert_eq!(first.dependencies(), VClock::new());
+    assert_eq!(second.dependencies(), Dot::new(1, 1).into());
+    assert_eq!(rm.dependencies(), Dot::new(1, 1).into());
+
+    // a minimal causal buffer, holding ops back until their dependencies are met
+    let mut b: Orswot<u8, u8> = Orswot::new();
+    let mut pending = vec![rm, second, first];
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|op| op.dependencies() <= b.read().add_clock)
+            .expect("an op should always be ready");
+        b.apply(pending.remove(ready));
+    }
+
+    assert_eq!(b, a);
+}
+
+#[test]
+fn test_merge_with_progress() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let mut b: Orswot<u8, u8> = Orswot::new();
+    for member in 0..10 {
+        let op = a.add(member, a.read().derive_add_ctx(1));
+        a.apply(op);
+        let op = b.add(member + 5, b.read().derive_add_ctx(2));
+        b.apply(op);
+    }
+
+    let mut merged = a.clone();
+    merged.merge(b.clone());
+
+    let mut reported = Vec::new();
+    a.merge_with_progress(b, &mut |fraction| reported.push(fraction));
+
+    assert_eq!(a, merged);
+    assert!(reported.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(reported.last(), Some(&1.0));
+}
+
+#[test]
+fn test_from_value() {
+    let set: Orswot<u8, u8> = Orswot::from_value(vec![1, 2, 3], 7);
+
+    assert_eq!(set.read().val, vec![1, 2, 3].into_iter().collect());
+
+    let dots: HashSet<u64> = [1, 2, 3]
+        .iter()
+        .map(|m| set.context_ref(m).unwrap().get(&7))
+        .collect();
+    assert_eq!(dots, vec![1, 2, 3].into_iter().collect());
+}
+
+#[test]
+fn test_range() {
+    let set: Orswot<u8, u8> = Orswot::from_value(0..20, 1);
+
+    let mut members: Vec<u8> = set.read().val.into_iter().collect();
+    members.sort();
+
+    let page: Vec<u8> = set.range(5..10).cloned().collect();
+    assert_eq!(page, &members[5..10]);
+
+    let tail: Vec<u8> = set.range(15..).cloned().collect();
+    assert_eq!(tail, &members[15..]);
+}
+
+#[test]
+fn test_deserialize_checked_rejects_corrupt_state() {
+    let set: Orswot<u8, u8> = Orswot::from_value(vec![1, 2], 1);
+    let json = serde_json::to_string(&set).unwrap();
+    let mut de = serde_json::Deserializer::from_str(&json);
+    assert_eq!(Orswot::deserialize_checked(&mut de).unwrap(), set);
+
+    // member 2 claims a dot the set clock has never seen
+    let corrupt = r#"{
+        "clock": {"dots": {"1": 2}},
+        "entries": {"1": {"dots": {"1": 1}}, "2": {"dots": {"1": 3}}},
+        "deferred": {}
+    }"#;
+    let mut de = serde_json::Deserializer::from_str(corrupt);
+    assert!(Orswot::<u8, u8>::deserialize_checked(&mut de).is_err());
+
+    let parsed: Orswot<u8, u8> = serde_json::from_str(corrupt).unwrap();
+    assert_eq!(parsed.validate(), Err(Error::InconsistentState));
+}
+
+#[test]
+fn test_merge_freezing_ignores_new_dots_from_frozen_actors() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let op = a.add(1, a.read().derive_add_ctx(1));
+    a.apply(op);
+
+    let mut b = a.clone();
+    let op = b.add(2, b.read().derive_add_ctx(1));
+    b.apply(op);
+    let op = b.add(3, b.read().derive_add_ctx(2));
+    b.apply(op);
+
+    let frozen = vec![1].into_iter().collect();
+    a.merge_freezing(b, &frozen);
+
+    assert_eq!(a.read().val, vec![1, 3].into_iter().collect());
+    assert_eq!(a.read().add_clock.get(&1), 1);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_patch_since() {
+    let mut a: Orswot<String, u8> = Orswot::from_value(vec!["x".into(), "y/z".into()], 1);
+    let previous = a.clone();
+
+    let mut b = a.clone();
+    let x = "x".to_string();
+    let op = b.rm(x.clone(), b.contains(&x).derive_rm_ctx());
+    b.apply(op);
+    let op = b.add("w".to_string(), b.read().derive_add_ctx(2));
+    b.apply(op);
+    a.merge(b);
+
+    let patch = a.json_patch_since(&previous).unwrap();
+
+    // apply the patch to a plain JSON model of the previous members
+    let mut members = serde_json::json!({ "x": "x", "y~1z": "y/z" });
+    for op in patch.as_array().unwrap() {
+        let key = op["path"].as_str().unwrap().trim_start_matches("/members/");
+        let members = members.as_object_mut().unwrap();
+        match op["op"].as_str().unwrap() {
+            "add" => assert!(members.insert(key.into(), op["value"].clone()).is_none()),
+            "remove" => assert!(members.remove(key).is_some()),
+            other => panic!("unexpected patch op {}", other),
+        }
+    }
+
+    let mut patched: Vec<&str> = members
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|v| v.as_str().unwrap())
+        .collect();
+    patched.sort();
+    assert_eq!(patched, vec!["w", "y/z"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_patch_since_fails_on_non_json_members() {
+    use std::collections::BTreeMap;
+
+    let member: BTreeMap<Vec<u8>, u8> = vec![(vec![1], 2)].into_iter().collect();
+    let a: Orswot<_, u8> = Orswot::from_value(vec![member], 1);
+    assert!(a.json_patch_since(&Orswot::new()).is_err());
+}
+
+#[test]
+fn test_apply_checking_gaps() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let first = a.add(1, a.read().derive_add_ctx(1));
+    assert_eq!(a.apply_checking_gaps(first.clone()), Ok(()));
+
+    // the op at counter 2 was lost in transit
+    let gapped = Op::Add {
+        dot: Dot::new(1, 3),
+        member: 3,
+    };
+    assert_eq!(a.apply_checking_gaps(gapped), Err(Error::CausalGap));
+    assert!(!a.contains(&3).val);
+
+    // redelivery is not a gap
+    assert_eq!(a.apply_checking_gaps(first), Ok(()));
+}
+
+#[test]
+fn test_merge_all_is_order_independent() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(vec![1, 2], 1);
+    let mut b = a.clone();
+    let mut c: Orswot<u8, u8> = Orswot::new();
+
+    let op = a.add(3, a.read().derive_add_ctx(2));
+    a.apply(op);
+    let op = b.rm(1, b.contains(&1).derive_rm_ctx());
+    b.apply(op);
+
+    // c sees a remove of 3 before the add it removes, so it is deferred
+    let op = a.rm(3, a.contains(&3).derive_rm_ctx());
+    c.apply(op);
+
+    let expected = Orswot::merge_all(vec![a.clone(), b.clone(), c.clone()]);
+    for replicas in &[
+        [a.clone(), c.clone(), b.clone()],
+        [b.clone(), a.clone(), c.clone()],
+        [c.clone(), b.clone(), a.clone()],
+    ] {
+        assert_eq!(Orswot::merge_all(replicas.iter().cloned()), expected);
+    }
+    assert_eq!(expected.read().val, vec![2].into_iter().collect());
+}
+
+#[test]
+fn test_serialize_canonical_is_byte_identical() {
+    let canonical = |set: &Orswot<u8, u8>| {
+        let mut bytes = Vec::new();
+        set.serialize_canonical(&mut serde_json::Serializer::new(&mut bytes))
+            .unwrap();
+        bytes
+    };
+
+    let mut a: Orswot<u8, u8> = Orswot::from_value(0..32, 1);
+    let mut b: Orswot<u8, u8> = Orswot::from_value(32..64, 2);
+    let c = a.clone();
+    a.apply(a.rm(3, a.contains(&3).derive_rm_ctx()));
+    b.apply(b.add(3, b.read().derive_add_ctx(2)));
+
+    let expected = canonical(&Orswot::merge_all(vec![a.clone(), b.clone(), c.clone()]));
+    for replicas in &[[c.clone(), b.clone(), a.clone()], [b, a, c]] {
+        assert_eq!(
+            canonical(&Orswot::merge_all(replicas.iter().cloned())),
+            expected
+        );
+    }
+
+    let parsed: Orswot<u8, u8> = serde_json::from_slice(&expected).unwrap();
+    assert_eq!(canonical(&parsed), expected);
+}
+
+#[test]
+fn test_contains_after_add_and_rm() {
+    let mut a: Orswot<&str, &str> = Orswot::new();
+    a.apply(a.add("x", a.read().derive_add_ctx("A")));
+    assert!(a.contains(&"x").val);
+
+    a.apply(a.rm("x", a.contains(&"x").derive_rm_ctx()));
+    assert!(!a.contains(&"x").val);
+}
+
+#[test]
+fn test_contains_member_added_by_two_actors() {
+    let mut a: Orswot<&str, &str> = Orswot::new();
+    let mut b = a.clone();
+    a.apply(a.add("x", a.read().derive_add_ctx("A")));
+    b.apply(b.add("x", b.read().derive_add_ctx("B")));
+    a.merge(b);
+
+    let ctx = a.contains(&"x");
+    assert!(ctx.val);
+    assert_eq!(ctx.rm_clock.get(&"A"), 1);
+    assert_eq!(ctx.rm_clock.get(&"B"), 1);
+}
+
+#[test]
+fn test_len_and_is_empty() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    assert!(a.is_empty().val);
+
+    for member in 0..3 {
+        a.apply(a.add(member, a.read().derive_add_ctx(1)));
+    }
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+
+    assert_eq!(a.len().val, 2);
+    assert!(!a.is_empty().val);
+}
+
+#[test]
+fn test_iter_yields_members() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(0..5, 1);
+    a.apply(a.rm(3, a.contains(&3).derive_rm_ctx()));
+
+    let members: HashSet<u8> = a.iter().cloned().collect();
+    assert_eq!(members, a.read().val);
+
+    let mut count = 0;
+    for member in &a {
+        assert!(members.contains(member));
+        count += 1;
+    }
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn test_clear() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(0..5, 1);
+    let clock = a.read().add_clock;
+
+    a.apply(a.clear(a.read().derive_rm_ctx()));
+
+    assert!(a.is_empty().val);
+    assert_eq!(a.read().add_clock, clock);
+}
+
+#[test]
+fn test_delta_converges_to_full_merge() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(0..10, 1);
+    let mut b = a.clone();
+    let since = b.read().add_clock;
+
+    a.apply(a.add(10, a.read().derive_add_ctx(1)));
+    a.apply(a.rm(3, a.contains(&3).derive_rm_ctx()));
+    // a remove from the future, it stays deferred on both sides
+    a.apply(Op::Rm {
+        clock: Dot::new(2, 1).into(),
+        members: vec![5].into_iter().collect(),
+    });
+    b.apply(b.add(11, b.read().derive_add_ctx(2)));
+
+    let delta = a.delta(&since);
+
+    let mut full = b.clone();
+    full.merge(a);
+    b.merge_delta(delta);
+
+    assert_eq!(b, full);
+}
+
+#[test]
+fn test_delta_carries_stale_remove() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(1, a.read().derive_add_ctx("B")));
+    let mut b = a.clone();
+
+    // a remove that only witnessed A's add of 1
+    a.apply(Op::Rm {
+        clock: Dot::new("A", 1).into(),
+        members: vec![1].into_iter().collect(),
+    });
+    assert_eq!(a.contains(&1).rm_clock, Dot::new("B", 1).into());
+
+    let mut full = b.clone();
+    full.merge(a.clone());
+    b.merge_delta(a.delta(&b.read().add_clock));
+
+    assert_eq!(b, full);
+    assert_eq!(b.contains(&1).rm_clock, Dot::new("B", 1).into());
+}
+
+quickcheck! {
+    fn prop_merge_delta_same_as_merge(
+        shared_prims: Vec<(u8, u8, u8, u64)>,
+        op_prims: Vec<(u8, u8, u8, u64)>
+    ) -> bool {
+        // few actors, members and counters so that the ops overlap
+        let narrow = |prims: Vec<(u8, u8, u8, u64)>| {
+            let prims = prims.into_iter().map(|(a, m, c, n)| (a % 3, m % 3, c, n % 4));
+            build_opvec(prims.collect()).ops
+        };
+        let mut a: Orswot<u8, u8> = Orswot::new();
+        a.apply_all(narrow(shared_prims).into_iter().map(|(_, op)| op));
+        let mut b = a.clone();
+        for (actor, op) in narrow(op_prims) {
+            if actor % 2 == 0 {
+                a.apply(op);
+            } else {
+                b.apply(op);
+            }
+        }
+
+        let mut full = b.clone();
+        full.merge(a.clone());
+        b.merge_delta(a.delta(&b.read().add_clock));
+        b == full
+    }
+}
+
+#[test]
+fn test_from_iter() {
+    use std::iter::FromIterator;
+
+    let set = Orswot::<u8, u8>::from_iter(vec![1, 2, 3]);
+    assert_eq!(set.read().val, vec![1, 2, 3].into_iter().collect());
+    assert_eq!(set.read().add_clock, Dot::new(0, 3).into());
+}
+
+#[test]
+fn test_retain() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(1..=5, 1);
+    let mut b = a.clone();
+
+    let ops = a.retain(|member| member % 2 == 0);
+    assert_eq!(ops.len(), 3);
+    for op in ops.iter().cloned() {
+        a.apply(op);
+    }
+    assert_eq!(a.read().val, vec![2, 4].into_iter().collect());
+
+    // a concurrent re-add survives the removes
+    b.apply(b.add(1, b.read().derive_add_ctx(2)));
+    for op in ops {
+        b.apply(op);
+    }
+    assert_eq!(b.read().val, vec![1, 2, 4].into_iter().collect());
+}
+
+#[test]
+fn test_union_and_intersection() {
+    let a: Orswot<u8, u8> = Orswot::from_value(vec![1, 2, 3], 1);
+    let b: Orswot<u8, u8> = Orswot::from_value(vec![3, 4], 2);
+    let c: Orswot<u8, u8> = Orswot::from_value(vec![5], 3);
+
+    assert_eq!(a.union(&b), vec![1, 2, 3, 4].into_iter().collect());
+    assert_eq!(a.intersection(&b), vec![3].into_iter().collect());
+
+    assert_eq!(a.union(&c), vec![1, 2, 3, 5].into_iter().collect());
+    assert!(a.intersection(&c).is_empty());
+}
+
+#[test]
+fn test_rm_member_only_removes_seen_version() {
+    let mut a: Orswot<&str, &str> = Orswot::new();
+    a.apply(a.add("x", a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    let rm = a.rm_member(&"x");
+    a.apply(rm.clone());
+    assert!(!a.contains(&"x").val);
+
+    // b concurrently re-added "x", the remove doesn't touch that version
+    b.apply(b.add("x", b.read().derive_add_ctx("B")));
+    b.apply(rm);
+    assert!(b.contains(&"x").val);
+    assert_eq!(b.contains(&"x").rm_clock, Dot::new("B", 1).into());
+}
+
+#[test]
+fn test_lru_orswot_evicts_identically_across_replicas() {
+    let mut a: orswot::LRUOrswot<u8, u8> = orswot::LRUOrswot::new(2);
+    let mut b = a.clone();
+
+    for member in 1..=3 {
+        for op in a.add(member, a.set().read().derive_add_ctx(1)) {
+            a.apply(op);
+        }
+    }
+    assert_eq!(a.set().read().val, vec![2, 3].into_iter().collect());
+
+    for member in 4..=5 {
+        for op in b.add(member, b.set().read().derive_add_ctx(2)) {
+            b.apply(op);
+        }
+    }
+
+    let mut ab = a.clone();
+    ab.merge(b.clone());
+    let mut ba = b;
+    ba.merge(a);
+    assert_eq!(ab, ba);
+
+    for op in ab.evict() {
+        ab.apply(op);
+    }
+    for op in ba.evict() {
+        ba.apply(op);
+    }
+    assert_eq!(ab, ba);
+    assert_eq!(ab.set().len().val, 2);
+    assert_eq!(ab.set().read().val, vec![3, 5].into_iter().collect());
+}
+
+#[test]
+fn test_read_returns_members_with_their_context() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(vec![1, 2], 1);
+    a.apply(a.add(3, a.read().derive_add_ctx(2)));
+
+    let read = a.read();
+    assert_eq!(read.val, vec![1, 2, 3].into_iter().collect());
+    assert_eq!(read.add_clock, read.rm_clock);
+    assert_eq!(read.rm_clock.get(&1), 2);
+    assert_eq!(read.rm_clock.get(&2), 1);
+
+    // the context covers every member that was read
+    for member in read.val.iter() {
+        assert!(a.contains(member).rm_clock <= read.rm_clock);
+    }
+}
+
+#[test]
+fn test_apply_all_replays_op_log_idempotently() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    let mut log = Vec::new();
+    for member in 0..4 {
+        let op = a.add(member, a.read().derive_add_ctx(1));
+        a.apply(op.clone());
+        log.push(op);
+    }
+    let op = a.rm_member(&2);
+    a.apply(op.clone());
+    log.push(op);
+
+    let mut once = Orswot::new();
+    once.apply_all(log.clone());
+    let mut twice = once.clone();
+    twice.apply_all(log);
+
+    assert_eq!(once, a);
+    assert_eq!(twice, once);
+}
+
+#[test]
+fn test_estimated_serialized_size_grows_with_the_set() {
+    let mut a: Orswot<u32, u8> = Orswot::new();
+    let mut last = a.estimated_serialized_size();
+    for member in 0..20 {
+        a.apply(a.add(member, a.read().derive_add_ctx((member % 3) as u8)));
+        let size = a.estimated_serialized_size();
+        assert!(size > last);
+        last = size;
+    }
+}
+
+#[test]
+fn test_diff_transforms_into_target() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let mut b: Orswot<u8, &str> = Orswot::new();
+    for member in 0..4 {
+        a.apply(a.add(member, a.read().derive_add_ctx("A")));
+    }
+    for member in 2..9 {
+        b.apply(b.add(member, b.read().derive_add_ctx("B")));
+    }
+
+    for (mut from, target) in [(a.clone(), b.clone()), (b, a)] {
+        for op in from.diff(&target) {
+            from.apply(op);
+        }
+        assert_eq!(from.read().val, target.read().val);
+        assert!(from.diff(&target).is_empty());
+    }
+}
+
+#[test]
+fn test_from_ops_matches_incremental_build() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let mut log = Vec::new();
+    for member in 0..5 {
+        let op = a.add(member, a.read().derive_add_ctx("A"));
+        a.apply(op.clone());
+        log.push(op);
+    }
+    let op = a.rm(3, a.contains(&3).derive_rm_ctx());
+    a.apply(op.clone());
+    log.push(op);
+
+    assert_eq!(Orswot::from_ops(log.clone()), a);
+
+    // counters of A skipped by the log, e.g. used by a sibling in a Map
+    log.remove(0);
+    let mut b = Orswot::new();
+    for op in log.clone() {
+        b.apply(op);
+    }
+    assert_eq!(Orswot::from_ops(log.clone()), b);
+    assert_eq!(b.read().val, vec![1, 2, 4].into_iter().collect());
+    assert_eq!(
+        Orswot::<u8, &str>::new().apply_checking_gaps(log[0].clone()),
+        Err(Error::CausalGap)
+    );
+}
+
+#[test]
+fn test_from_ops_rebuilds_diff() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let empty = a.clone();
+    for member in 0..3 {
+        let op = a.add(member, a.read().derive_add_ctx("A"));
+        a.apply(op);
+    }
+    let op = a.rm(0, a.contains(&0).derive_rm_ctx());
+    a.apply(op);
+
+    assert_eq!(Orswot::from_ops(empty.diff(&a)).read().val, a.read().val);
+}
+
+#[test]
+fn test_iter_matches_read() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    assert_eq!(a.iter().len(), 0);
+    for member in 0..6 {
+        a.apply(a.add(member, a.read().derive_add_ctx("A")));
+    }
+    a.apply(a.rm(2, a.contains(&2).derive_rm_ctx()));
+
+    let members = a.iter();
+    assert_eq!(members.len(), 5);
+    assert_eq!(members.cloned().collect::<HashSet<_>>(), a.read().val);
+}
+
+#[test]
+fn test_rm_op_serde_round_trip() {
+    let clock: VClock<String> = vec![Dot::new("b".to_string(), 2), Dot::new("a".to_string(), 1)]
+        .into_iter()
+        .collect();
+    let op: Op<u8, String> = Op::Rm {
+        clock,
+        members: vec![7].into_iter().collect(),
+    };
+
+    let json = serde_json::to_value(&op).unwrap();
+    #[cfg(not(feature = "compact-ops"))]
+    let expected = serde_json::json!({
+        "Rm": { "clock": { "dots": { "a": 1, "b": 2 } }, "members": [7] }
+    });
+    #[cfg(feature = "compact-ops")]
+    let expected = serde_json::json!({
+        "Rm": { "clock": [["a", 1], ["b", 2]], "members": [7] }
+    });
+    assert_eq!(json, expected);
+    assert_eq!(serde_json::from_value::<Op<u8, String>>(json).unwrap(), op);
+}
+
+#[cfg(feature = "compact-ops")]
+#[test]
+fn test_compact_clock_rejects_unsorted_actors() {
+    let unsorted = r#"{"Rm": {"clock": [["b", 2], ["a", 1]], "members": [7]}}"#;
+    assert!(serde_json::from_str::<Op<u8, String>>(unsorted).is_err());
+
+    let zero = r#"{"Rm": {"clock": [["a", 0]], "members": [7]}}"#;
+    assert!(serde_json::from_str::<Op<u8, String>>(zero).is_err());
+
+    // the state of a set keeps the derived encoding
+    let set: Orswot<u8, String> = Orswot::from_value(vec![1], "a".to_string());
+    let json = serde_json::to_value(&set).unwrap();
+    assert_eq!(json["clock"], serde_json::json!({ "dots": { "a": 1 } }));
+}
+
+#[test]
+fn test_rm_checked_rejects_stale_ctx() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let stale = a.contains(&1).derive_rm_ctx();
+
+    // the member is re-added after the ctx was read
+    a.apply(a.add(1, a.read().derive_add_ctx("B")));
+    assert_eq!(a.rm_checked(1, stale), Err(Error::StaleContext));
+
+    let op = a.rm_checked(1, a.contains(&1).derive_rm_ctx()).unwrap();
+    a.apply(op);
+    assert!(!a.contains(&1).val);
+
+    // removing an absent member is accepted, like `rm`
+    assert!(a.rm_checked(2, a.contains(&2).derive_rm_ctx()).is_ok());
+}
+
+#[test]
+fn test_contains_ctx_clocks() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("B")));
+
+    let present = a.contains(&1);
+    assert_eq!(present.add_clock, a.read().add_clock);
+    assert_eq!(present.rm_clock, Dot::new("A", 1).into());
+    assert_eq!(Some(&present.rm_clock), a.context_ref(&1));
+
+    let absent = a.contains(&3);
+    assert_eq!(absent.add_clock, a.read().add_clock);
+    assert_eq!(absent.rm_clock, VClock::new());
+    assert_eq!(a.context_ref(&3), None);
+    assert_eq!(absent.derive_add_ctx("A").dot, Dot::new("A", 2));
+}
+
+#[test]
+fn test_extend_with_ops() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let mut ops = Vec::new();
+    for member in 0..4 {
+        let op = a.add(member, a.read().derive_add_ctx("A"));
+        a.apply(op.clone());
+        ops.push(op);
+    }
+    ops.push(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    // replayed ops are no-ops
+    ops.push(ops[0].clone());
+
+    let mut folded = Orswot::new();
+    for op in ops.iter().cloned() {
+        folded.apply(op);
+    }
+
+    let mut extended = Orswot::new();
+    extended.extend(ops);
+    assert_eq!(extended, folded);
+}
+
+#[test]
+fn test_actor_counter() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    assert_eq!(a.actor_counter(&"x"), 0);
+
+    for member in 0..3 {
+        a.apply(a.add(member, a.read().derive_add_ctx("x")));
+    }
+    a.apply(a.add(9, a.read().derive_add_ctx("y")));
+    a.apply(a.rm(2, a.contains(&2).derive_rm_ctx()));
+
+    assert_eq!(a.actor_counter(&"x"), 3);
+    assert_eq!(a.actor_counter(&"y"), 1);
+    assert_eq!(a.actor_counter(&"z"), 0);
+}
+
+#[test]
+fn test_members_as_of() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("A")));
+    let earlier = a.read().add_clock;
+    a.apply(a.add(3, a.read().derive_add_ctx("B")));
+    // re-adding moves the member past the earlier clock
+    a.apply(a.add(1, a.read().derive_add_ctx("B")));
+
+    assert_eq!(a.members_as_of(&a.read().add_clock), a.read().val);
+    assert_eq!(a.members_as_of(&earlier), vec![2].into_iter().collect());
+    assert!(a.members_as_of(&VClock::new()).is_empty());
+}
+
+#[test]
+fn test_entries_pairs_members_with_clocks() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("B")));
+    a.apply(a.add(1, a.read().derive_add_ctx("B")));
+
+    let mut entries: Vec<(u8, VClock<&str>)> = a
+        .entries()
+        .map(|(member, clock)| (*member, clock.clone()))
+        .collect();
+    entries.sort_by_key(|(member, _)| *member);
+
+    assert_eq!(
+        entries,
+        vec![
+            (
+                1,
+                vec![Dot::new("A", 1), Dot::new("B", 2)]
+                    .into_iter()
+                    .collect()
+            ),
+            (2, Dot::new("B", 1).into()),
+        ]
+    );
+}
+
+#[test]
+fn test_apply_changed() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let add = a.add(1, a.read().derive_add_ctx("A"));
+    assert!(a.apply_changed(add.clone()));
+    assert!(!a.apply_changed(add));
+
+    let rm = a.rm(1, a.contains(&1).derive_rm_ctx());
+    assert!(a.apply_changed(rm.clone()));
+    assert!(!a.apply_changed(rm));
+
+    // a remove from the future is deferred once
+    let rm = Op::Rm {
+        clock: Dot::new("B", 1).into(),
+        members: vec![2].into_iter().collect(),
+    };
+    assert!(a.apply_changed(rm.clone()));
+    assert!(!a.apply_changed(rm));
+}
+
+quickcheck! {
+    fn prop_apply_changed_matches_state_change(op_prims: Vec<(u8, u8, u8, u64)>) -> bool {
+        let mut set: Orswot<u8, u8> = Orswot::new();
+        build_opvec(op_prims).ops.into_iter().all(|(_, op)| {
+            let before = set.clone();
+            set.apply_changed(op) == (set != before)
+        })
+    }
+}
+
+#[test]
+fn test_op_log_replication_matches_state_replication() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    let mut b: Orswot<u8, &str> = Orswot::new();
+    let mut a_log = Vec::new();
+    let mut b_log = Vec::new();
+
+    for member in 0..4 {
+        let op = a.add(member, a.read().derive_add_ctx("A"));
+        a.apply(op.clone());
+        a_log.push(op);
+    }
+    b.apply_all(a_log.clone());
+
+    let op = b.rm(2, b.contains(&2).derive_rm_ctx());
+    b.apply(op.clone());
+    b_log.push(op);
+    let op = b.add(9, b.read().derive_add_ctx("B"));
+    b.apply(op.clone());
+    b_log.push(op);
+
+    let op = a.add(2, a.read().derive_add_ctx("A"));
+    a.apply(op.clone());
+    a_log.push(op);
+
+    // a third replica receives b's ops before a's, the remove is deferred
+    let mut from_ops = Orswot::new();
+    from_ops.apply_all(b_log.iter().cloned());
+    from_ops.apply_all(a_log.iter().cloned());
+
+    let mut from_state = a.clone();
+    from_state.merge(b.clone());
+
+    assert_eq!(from_ops.read().val, from_state.read().val);
+    assert_eq!(from_ops, from_state);
+}
+
+#[test]
+fn test_add_ctx_clock_is_the_clock_after_the_add() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("B")));
+
+    let ctx = a.read().derive_add_ctx("A");
+    let expected = ctx.clock.clone();
+    a.apply(a.add(3, ctx));
+    assert_eq!(a.read().add_clock, expected);
+}
+
+#[test]
+fn test_forget_own_clock_empties_set() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    for member in 0..3 {
+        a.apply(a.add(member, a.read().derive_add_ctx("A")));
+    }
+    a.apply(a.add(7, a.read().derive_add_ctx("B")));
+    a.apply(a.rm(0, a.contains(&0).derive_rm_ctx()));
+
+    let mut partial = a.clone();
+    partial.forget(&Dot::new("A", 3).into());
+    assert_eq!(partial.read().val, vec![7].into_iter().collect());
+
+    let clock = a.read().add_clock;
+    a.forget(&clock);
+    assert!(a.is_empty().val);
+    assert_eq!(a, Orswot::new());
+}
+
+#[test]
+fn test_difference() {
+    let a: Orswot<u8, u8> = Orswot::from_value(vec![1, 2, 3], 1);
+    let b: Orswot<u8, u8> = Orswot::from_value(vec![3, 4], 2);
+
+    assert_eq!(a.difference(&b), vec![1, 2].into_iter().collect());
+    assert_eq!(b.difference(&a), vec![4].into_iter().collect());
+    assert!(a.difference(&a).is_empty());
+    assert_eq!(a.difference(&b), &a.read().val - &b.read().val);
+}
+
+#[test]
+fn test_rename_actor() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("old")));
+    a.apply(a.add(2, a.read().derive_add_ctx("b")));
+    let mut b = a.clone();
+    a.apply(a.add(3, a.read().derive_add_ctx("old")));
+    b.apply(b.rm(1, b.contains(&1).derive_rm_ctx()));
+
+    let mut merged_then_renamed = a.clone();
+    merged_then_renamed.merge(b.clone());
+    assert_eq!(merged_then_renamed.rename_actor(&"old", "new"), Ok(()));
+
+    assert_eq!(a.rename_actor(&"old", "new"), Ok(()));
+    assert_eq!(b.rename_actor(&"old", "new"), Ok(()));
+    assert_eq!(a.context_ref(&3), Some(&Dot::new("new", 2).into()));
+    assert_eq!(a.actor_counter(&"old"), 0);
+    assert_eq!(a.actor_counter(&"new"), 2);
+
+    a.merge(b);
+    assert_eq!(a, merged_then_renamed);
+    assert_eq!(a.read().val, vec![2, 3].into_iter().collect());
+
+    // dots of a known actor can't be reused
+    let before = a.clone();
+    assert_eq!(a.rename_actor(&"b", "new"), Err(Error::ConflictingMarker));
+    assert_eq!(a, before);
+}
+
+#[test]
+fn test_context_ref_shows_concurrent_adds() {
+    let mut a = Orswot::new();
+    let mut b = a.clone();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+    b.apply(b.add(2, b.read().derive_add_ctx("B")));
+    a.merge(b);
+
+    let clock = a.context_ref(&1).unwrap();
+    assert_eq!(clock.get(&"A"), 1);
+    assert_eq!(clock.get(&"B"), 1);
+    assert_eq!(clock.iter().count(), 2);
+
+    assert_eq!(a.context_ref(&2), Some(&Dot::new("B", 2).into()));
+    assert_eq!(a.context_ref(&3), None);
+}
+
+#[test]
+fn test_merged_matches_clone_then_merge() {
+    let mut a = Orswot::new();
+    let mut b = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    b.apply(b.add(2, b.read().derive_add_ctx("B")));
+    b.apply(b.rm(2, b.contains(&2).derive_rm_ctx()));
+    b.apply(b.add(3, b.read().derive_add_ctx("B")));
+
+    let merged = a.merged(&b);
+
+    let mut expected = a.clone();
+    expected.merge(b.clone());
+    assert_eq!(merged, expected);
+    assert_eq!(a.read().val, vec![1].into_iter().collect());
+    assert_eq!(b.read().val, vec![3].into_iter().collect());
+}
+
+#[test]
+fn test_versioned_op_round_trip() {
+    let set: Orswot<u8, String> = Orswot::new();
+    let op = set.add(1, set.read().derive_add_ctx("A".to_string()));
+
+    let json = serde_json::to_string(&op.clone().into_versioned()).unwrap();
+    let versioned: orswot::VersionedOp<u8, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(versioned.version, orswot::OP_VERSION);
+    assert_eq!(Op::from_versioned(versioned), Ok(op));
+}
+
+#[test]
+fn test_versioned_op_rejects_future_version() {
+    let set: Orswot<u8, String> = Orswot::new();
+    let mut versioned = set
+        .add(1, set.read().derive_add_ctx("A".to_string()))
+        .into_versioned();
+    versioned.version = orswot::OP_VERSION + 1;
+
+    let json = serde_json::to_string(&versioned).unwrap();
+    let versioned = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        Op::<u8, String>::from_versioned(versioned),
+        Err(Error::UnsupportedVersion)
+    );
+}
+
+#[test]
+fn test_apply_rm_member_reports_removal() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let rm_clock = a.contains(&1).rm_clock;
+
+    assert!(a.apply_rm_member(&1, &rm_clock));
+    assert!(!a.contains(&1).val);
+    assert!(!a.apply_rm_member(&1, &rm_clock));
+
+    // a remove from the future is deferred, it doesn't remove anything yet
+    let future: VClock<_> = Dot::new("B", 1).into();
+    assert!(!a.apply_rm_member(&2, &future));
+    a.apply(Op::Add {
+        dot: Dot::new("B", 1),
+        member: 2,
+    });
+    assert!(!a.contains(&2).val);
+}
+
+#[test]
+fn test_clear_keeps_concurrent_re_adds() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(1..=3, 1);
+    let mut b = a.clone();
+
+    a.apply(a.clear(a.read().derive_rm_ctx()));
+    assert!(a.is_empty().val);
+
+    b.apply(b.add(2, b.read().derive_add_ctx(2)));
+    a.merge(b);
+    assert_eq!(a.read().val, vec![2].into_iter().collect());
+}
+
+#[test]
+fn test_map_members_keeps_clocks() {
+    let mut a: Orswot<u8, u8> = Orswot::from_value(vec![1, 2, 3], 1);
+    a.apply(a.rm(2, a.contains(&2).derive_rm_ctx()));
+    a.apply(Op::Rm {
+        clock: Dot::new(2, 1).into(),
+        members: vec![4].into_iter().collect(),
+    });
+
+    let mut mapped = a.map_members(|m| m + 100);
+
+    assert_eq!(mapped.read().val, vec![101, 103].into_iter().collect());
+    assert_eq!(mapped.read().add_clock, a.read().add_clock);
+    for member in &[1, 3] {
+        assert_eq!(mapped.context_ref(&(member + 100)), a.context_ref(member));
+    }
+
+    // the deferred remove is translated along with the members
+    mapped.apply(Op::Add {
+        dot: Dot::new(2, 1),
+        member: 104,
+    });
+    assert!(!mapped.contains(&104).val);
+}
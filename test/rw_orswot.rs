@@ -0,0 +1,110 @@
+use crdts::{rw_orswot::Op, *};
+
+use super::testkit;
+
+fn build_replicas(op_prims: Vec<(u8, u8, u8)>) -> Vec<RWOrswot<u8, u8>> {
+    let mut replicas: Vec<RWOrswot<u8, u8>> = vec![RWOrswot::new(); 3];
+    for (replica, member, kind) in op_prims {
+        let actor = replica % 3;
+        let i = actor as usize;
+        let set = &replicas[i];
+        let op = match kind % 3 {
+            0 => set.add(member % 8, set.read().derive_add_ctx(actor)),
+            1 => set.rm(member % 8, set.read().derive_add_ctx(actor)),
+            _ => {
+                let peer = replicas[(member % 3) as usize].clone();
+                replicas[i].merge(peer);
+                continue;
+            }
+        };
+        replicas[i].apply(op);
+    }
+    replicas
+}
+
+quickcheck! {
+    fn prop_merge_commutative(op_prims: Vec<(u8, u8, u8)>) -> bool {
+        let replicas = build_replicas(op_prims);
+        let (a, b) = (&replicas[0], &replicas[1]);
+        a.merged(b) == b.merged(a)
+    }
+
+    fn prop_merge_associative(op_prims: Vec<(u8, u8, u8)>) -> bool {
+        let replicas = build_replicas(op_prims);
+        let (a, b, c) = (&replicas[0], &replicas[1], &replicas[2]);
+        a.merged(b).merged(c) == a.merged(&b.merged(c))
+    }
+
+    fn prop_merge_idempotent(op_prims: Vec<(u8, u8, u8)>) -> bool {
+        let replicas = build_replicas(op_prims);
+        replicas[0].merged(&replicas[0]) == replicas[0]
+    }
+
+    fn prop_merge_converges(op_prims: Vec<(u8, u8, bool)>) -> bool {
+        // ops are generated by a single replica, the witness tells which
+        // replica they are delivered to
+        let mut set: RWOrswot<u8, u8> = RWOrswot::new();
+        let mut ops = Vec::new();
+        for (actor, member, is_add) in op_prims {
+            let ctx = set.read().derive_add_ctx(actor % 4);
+            let op = if is_add {
+                set.add(member % 8, ctx)
+            } else {
+                set.rm(member % 8, ctx)
+            };
+            set.apply(op.clone());
+            ops.push((actor, op));
+        }
+        testkit::merge_converges::<RWOrswot<u8, u8>>(&ops)
+    }
+}
+
+#[test]
+fn test_rm_wins_over_concurrent_add() {
+    let mut a: RWOrswot<u8, &str> = RWOrswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    a.apply(a.rm(1, a.read().derive_add_ctx("A")));
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+    assert!(b.contains(&1).val);
+
+    a.merge(b.clone());
+    b.merge(a.clone());
+    assert!(!a.contains(&1).val);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_add_after_rm_survives() {
+    let mut a: RWOrswot<u8, &str> = RWOrswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.rm(1, a.read().derive_add_ctx("A")));
+    assert!(!a.contains(&1).val);
+
+    let mut b = a.clone();
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+    a.merge(b);
+    assert_eq!(a.read().val, vec![1].into_iter().collect());
+}
+
+#[test]
+fn test_redelivered_add_stays_removed() {
+    let mut a: RWOrswot<u8, &str> = RWOrswot::new();
+    let add = a.add(1, a.read().derive_add_ctx("A"));
+    a.apply(add.clone());
+    a.apply(a.rm(1, a.read().derive_add_ctx("B")));
+
+    a.apply(add);
+    assert!(!a.contains(&1).val);
+
+    // a new add has seen the remove
+    assert_eq!(
+        a.add(1, a.read().derive_add_ctx("A")),
+        Op::Add {
+            dot: Dot::new("A", 2),
+            member: 1,
+            seen_rms: Dot::new("B", 1).into(),
+        }
+    );
+}
@@ -3,7 +3,10 @@ extern crate quickcheck;
 
 extern crate crdts;
 
+mod annotated_orswot;
 mod map;
 mod mvreg;
 mod orswot;
+mod rw_orswot;
+mod testkit;
 mod vclock;
@@ -0,0 +1,54 @@
+use std::fmt::Debug;
+
+use crdts::*;
+
+const ACTOR_MAX: u8 = 11;
+
+// Different interleavings of ops applied to different
+// replicas should all converge when merged. Apply the
+// ops to increasing numbers of witnessing replicas,
+// then merge them together and make sure they have
+// all converged.
+//
+// Each op is paired with the actor that produced it, the
+// ops of an actor are all applied to the same witness.
+pub fn merge_converges<T>(ops: &[(u8, T::Op)]) -> bool
+where
+    T: CmRDT + CvRDT + Default + PartialEq + Debug,
+    T::Op: Clone + Debug,
+{
+    let mut result = None;
+    for i in 2..ACTOR_MAX {
+        let mut witnesses: Vec<T> = (0..i).map(|_| T::default()).collect();
+        for (actor, op) in ops.iter() {
+            let witness = &mut witnesses[(actor % i) as usize];
+            witness.apply(op.clone());
+        }
+        let mut merged = T::default();
+        for witness in witnesses {
+            merged.merge(witness);
+        }
+
+        if let Some(ref prev_res) = result {
+            if prev_res != &merged {
+                println!("ops: {:?}", ops);
+                println!("result: {:?}", result);
+                println!("merged: {:?}", merged);
+                return false;
+            };
+        } else {
+            result = Some(merged);
+        }
+    }
+    true
+}
+
+quickcheck! {
+    fn prop_gcounter_merge_converges(op_prims: Vec<(u8, u64)>) -> bool {
+        let ops: Vec<(u8, Dot<u8>)> = op_prims
+            .into_iter()
+            .map(|(actor, counter)| (actor, Dot::new(actor, counter)))
+            .collect();
+        merge_converges::<GCounter<u8>>(&ops)
+    }
+}
@@ -88,15 +88,9 @@ quickcheck! {
         a.forget(&b);
 
         if a.is_empty() {
-            match a.partial_cmp(&b) {
-                Some(Ordering::Less) | Some(Ordering::Equal) => true,
-                _ => false
-            }
+            matches!(a.partial_cmp(&b), Some(Ordering::Less) | Some(Ordering::Equal))
         } else {
-            match a.partial_cmp(&b) {
-                None | Some(Ordering::Greater) => true,
-                _ => false
-            }
+            matches!(a.partial_cmp(&b), None | Some(Ordering::Greater))
         }
     }
 }
@@ -173,6 +167,7 @@ fn test_merge_same_id() {
 }
 
 #[test]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
 fn test_vclock_ordering() {
     assert_eq!(VClock::<i8>::new(), VClock::new());
 
@@ -229,3 +224,294 @@ fn test_vclock_ordering() {
     assert!(!(a > b));
     assert_eq!(a, b);
 }
+
+#[test]
+fn test_try_from_btreemap() {
+    use std::collections::BTreeMap;
+    use std::convert::TryFrom;
+
+    let dots: BTreeMap<u8, u64> = vec![(1, 2), (2, 1)].into_iter().collect();
+    let clock = VClock::try_from(dots).unwrap();
+    assert_eq!(clock.get(&1), 2);
+    assert_eq!(clock.get(&2), 1);
+
+    let dots: BTreeMap<u8, u64> = vec![(1, 2), (2, 0)].into_iter().collect();
+    assert_eq!(VClock::try_from(dots), Err(Error::ZeroCounter));
+}
+
+#[test]
+fn test_try_from_vec() {
+    use std::convert::TryFrom;
+
+    let clock = VClock::try_from(vec![(2, 1), (1, 2)]).unwrap();
+    assert_eq!(
+        clock,
+        vec![Dot::new(1, 2), Dot::new(2, 1)].into_iter().collect()
+    );
+
+    assert_eq!(
+        VClock::try_from(vec![(1, 2), (2, 1), (1, 3)]),
+        Err(Error::DuplicateActor)
+    );
+    assert_eq!(VClock::try_from(vec![(1, 0)]), Err(Error::ZeroCounter));
+}
+
+#[test]
+fn test_partial_cmp_with_disjoint_actors() {
+    let a: VClock<&str> = vec![Dot::new("A", 1)].into_iter().collect();
+    let ab: VClock<&str> = vec![Dot::new("A", 1), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+    let b: VClock<&str> = Dot::new("B", 1).into();
+
+    assert_eq!(a.partial_cmp(&a.clone()), Some(Ordering::Equal));
+    assert_eq!(a.partial_cmp(&ab), Some(Ordering::Less));
+    assert_eq!(ab.partial_cmp(&a), Some(Ordering::Greater));
+    assert_eq!(a.partial_cmp(&b), None);
+    assert!(VClock::new() < b);
+}
+
+#[test]
+fn test_concurrent() {
+    let a: VClock<&str> = Dot::new("a", 1).into();
+    let b: VClock<&str> = Dot::new("b", 1).into();
+    let ab: VClock<&str> = vec![Dot::new("a", 1), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+
+    assert!(a.concurrent(&b));
+    assert!(b.concurrent(&a));
+    assert!(!a.concurrent(&ab));
+    assert!(!ab.concurrent(&a));
+    assert!(!a.concurrent(&a));
+}
+
+#[test]
+fn test_glb() {
+    let mut a: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 2)]
+        .into_iter()
+        .collect();
+    let b: VClock<&str> = vec![Dot::new("a", 1), Dot::new("b", 5)]
+        .into_iter()
+        .collect();
+    a.glb(&b);
+    assert_eq!(
+        a,
+        vec![Dot::new("a", 1), Dot::new("b", 2)]
+            .into_iter()
+            .collect()
+    );
+
+    let c: VClock<&str> = Dot::new("c", 1).into();
+    a.glb(&c);
+    assert!(a.is_empty());
+}
+
+#[test]
+fn test_lub() {
+    let a: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 2)]
+        .into_iter()
+        .collect();
+    let b: VClock<&str> = vec![Dot::new("a", 1), Dot::new("b", 5)]
+        .into_iter()
+        .collect();
+
+    let lub = a.lub(&b);
+    assert_eq!(
+        lub,
+        vec![Dot::new("a", 3), Dot::new("b", 5)]
+            .into_iter()
+            .collect()
+    );
+
+    let mut merged = a.clone();
+    merged.merge(b);
+    assert_eq!(lub, merged);
+}
+
+#[test]
+fn test_iter_and_actors() {
+    let mut clock = VClock::new();
+    clock.apply(clock.inc("b"));
+    clock.apply(clock.inc("a"));
+    clock.apply(clock.inc("b"));
+
+    let dots: Vec<(&str, u64)> = clock.iter().map(|dot| (*dot.actor, dot.counter)).collect();
+    assert_eq!(dots, vec![("a", 1), ("b", 2)]);
+
+    let actors: Vec<&&str> = clock.actors().collect();
+    assert_eq!(actors, vec![&"a", &"b"]);
+}
+
+#[test]
+fn test_dot() {
+    let clock: VClock<&str> = Dot::new("a", 4).into();
+
+    assert_eq!(clock.dot(&"a").counter, 4);
+    assert_eq!(clock.dot(&"b"), Dot::new("b", 0));
+    assert_eq!(clock.inc("a").counter, 5);
+}
+
+#[test]
+fn test_clone_without_resets_seen_dots() {
+    let clock: VClock<&str> = vec![Dot::new("a", 2), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+    let dominating: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+    let concurrent: VClock<&str> = vec![Dot::new("a", 1), Dot::new("c", 4)]
+        .into_iter()
+        .collect();
+
+    assert!(clock.clone_without(&dominating).is_empty());
+    assert_eq!(clock.clone_without(&concurrent), clock);
+}
+
+#[test]
+fn test_truncate_to_returns_dropped_dots() {
+    let original: VClock<&str> = vec![Dot::new("a", 2), Dot::new("b", 5), Dot::new("c", 1)]
+        .into_iter()
+        .collect();
+    let compacted: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 4)]
+        .into_iter()
+        .collect();
+
+    let mut clock = original.clone();
+    let dropped = clock.truncate_to(&compacted);
+
+    assert_eq!(dropped, Dot::new("a", 2).into());
+    assert_eq!(clock, original.clone_without(&compacted));
+    assert_eq!(dropped.lub(&clock), original);
+}
+
+#[test]
+fn test_dot_increment_and_ordering() {
+    let dot = Dot::new("a", 3);
+    assert_eq!(dot.clone().increment(), Dot::new("a", 4));
+    assert!(dot < dot.clone().increment());
+
+    let mut dots = vec![
+        Dot::new("b", 1),
+        Dot::new("a", 7),
+        Dot::new("b", 0),
+        Dot::new("a", 2),
+    ];
+    dots.sort();
+    assert_eq!(
+        dots,
+        vec![
+            Dot::new("a", 2),
+            Dot::new("a", 7),
+            Dot::new("b", 0),
+            Dot::new("b", 1),
+        ]
+    );
+}
+
+#[test]
+fn test_compare() {
+    use crdts::vclock::VClockOrdering;
+
+    let a: VClock<&str> = vec![Dot::new("a", 2), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+    let ahead: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+    let diverged: VClock<&str> = vec![Dot::new("a", 1), Dot::new("b", 2)]
+        .into_iter()
+        .collect();
+    let disjoint: VClock<&str> = Dot::new("c", 1).into();
+
+    assert_eq!(a.compare(&a.clone()), VClockOrdering::Equal);
+    assert_eq!(
+        VClock::<&str>::new().compare(&VClock::new()),
+        VClockOrdering::Equal
+    );
+    assert_eq!(a.compare(&ahead), VClockOrdering::Less);
+    assert_eq!(ahead.compare(&a), VClockOrdering::Greater);
+    assert_eq!(VClock::new().compare(&a), VClockOrdering::Less);
+    assert_eq!(a.compare(&diverged), VClockOrdering::Concurrent);
+    assert_eq!(a.compare(&disjoint), VClockOrdering::Concurrent);
+    assert_eq!(disjoint.compare(&a), VClockOrdering::Concurrent);
+    assert_eq!(a.compare(&a.lub(&disjoint)), VClockOrdering::Less);
+}
+
+#[test]
+fn test_apply_changed_default() {
+    let mut clock = VClock::new();
+    assert!(clock.apply_changed(Dot::new("a", 2)));
+    assert!(!clock.apply_changed(Dot::new("a", 2)));
+    assert!(!clock.apply_changed(Dot::new("a", 1)));
+}
+
+#[test]
+fn test_apply_advances_to_a_remote_counter() {
+    let mut clock: VClock<&str> = vec![Dot::new("a", 2), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+
+    // catching up skips straight to the remote counter
+    clock.apply(Dot::new("a", 7));
+    assert_eq!(clock.get(&"a"), 7);
+
+    // moving backwards is a no-op
+    clock.apply(Dot::new("a", 3));
+    assert_eq!(clock.get(&"a"), 7);
+    assert_eq!(clock.get(&"b"), 1);
+}
+
+#[test]
+fn test_rename_actor() {
+    let mut clock: VClock<&str> = vec![Dot::new("old", 4), Dot::new("b", 1)]
+        .into_iter()
+        .collect();
+    clock.rename_actor(&"old", "new");
+    assert_eq!(
+        clock,
+        vec![Dot::new("new", 4), Dot::new("b", 1)]
+            .into_iter()
+            .collect()
+    );
+
+    // renaming onto a known actor keeps the greater counter
+    clock.rename_actor(&"b", "new");
+    assert_eq!(clock, Dot::new("new", 4).into());
+
+    // renaming an absent actor is a no-op
+    clock.rename_actor(&"c", "d");
+    assert_eq!(clock, Dot::new("new", 4).into());
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Dot::new("a", 1).to_string(), "a:1");
+
+    let clock: VClock<&str> = vec![Dot::new("b", 3), Dot::new("a", 1)]
+        .into_iter()
+        .collect();
+    assert_eq!(clock.to_string(), "<a:1, b:3>");
+    assert_eq!(VClock::<u8>::new().to_string(), "<>");
+}
+
+#[test]
+fn test_dot_from_tuple() {
+    let dot: Dot<&str> = ("A", 3).into();
+    assert_eq!(dot, Dot::new("A", 3));
+    assert_eq!(Dot::from(("A", 3)), Dot::new("A", 3));
+}
+
+#[test]
+fn test_collect_clocks_into_lub() {
+    let clocks: Vec<VClock<&str>> = vec![
+        Dot::new("a", 1).into(),
+        Dot::new("b", 2).into(),
+        Dot::new("a", 3).into(),
+    ];
+    let lub: VClock<&str> = clocks.into_iter().collect();
+    let expected: VClock<&str> = vec![Dot::new("a", 3), Dot::new("b", 2)]
+        .into_iter()
+        .collect();
+    assert_eq!(lub, expected);
+}